@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use rusoto_core::Region;
+use rusoto_credential::{AutoRefreshingProvider, AwsCredentials, CredentialsError, DefaultCredentialsProvider, ProfileProvider, ProvideAwsCredentials, Variable};
+use rusoto_datapipeline::DataPipelineClient;
+use rusoto_s3::S3Client;
+use rusoto_sts::{StsAssumeRoleSessionCredentialsProvider, StsClient, WebIdentityProvider};
+use crate::clients::clients::{http_client, static_provider};
+use crate::clients::models::client_target::{AssumeRole, ClientKey, ClientTarget, CredentialSource};
+use crate::errors::models::error_response::ErrorResponse;
+
+/// A credentials provider of any type, so each link of an assume-role chain
+/// can be built on the one before and cached alongside it.
+#[derive(Clone)]
+pub struct SharedCredentials(Arc<dyn ProvideAwsCredentials + Send + Sync>);
+
+impl SharedCredentials {
+    fn new<P: ProvideAwsCredentials + Send + Sync + 'static>(provider: P) -> SharedCredentials {
+        SharedCredentials(Arc::new(provider))
+    }
+}
+
+#[async_trait]
+impl ProvideAwsCredentials for SharedCredentials {
+    async fn credentials(&self) -> Result<AwsCredentials, CredentialsError> {
+        self.0.credentials().await
+    }
+}
+
+/// Builds clients for any region and role chain from one credential source.
+/// Credentials are refreshed as they expire, and both credentials and clients
+/// are reused for targets with the same account, region and role.
+pub struct ClientFactory {
+    source: CredentialSource,
+    credentials: Mutex<HashMap<ClientKey, SharedCredentials>>,
+    s3_clients: Mutex<HashMap<ClientKey, S3Client>>,
+    datapipeline_clients: Mutex<HashMap<ClientKey, DataPipelineClient>>,
+}
+
+impl ClientFactory {
+    pub fn new(source: CredentialSource) -> ClientFactory {
+        ClientFactory {
+            source,
+            credentials: Mutex::new(HashMap::new()),
+            s3_clients: Mutex::new(HashMap::new()),
+            datapipeline_clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn profile(name: &str) -> ClientFactory {
+        ClientFactory::new(CredentialSource::Profile(name.to_string()))
+    }
+
+    pub fn s3(&self, target: &ClientTarget) -> Result<S3Client, String> {
+        cached(&self.s3_clients, target.cache_key(), || {
+            Ok(S3Client::new_with(http_client()?, self.credentials(target)?, region(target)?))
+        })
+    }
+
+    pub fn datapipeline(&self, target: &ClientTarget) -> Result<DataPipelineClient, String> {
+        cached(&self.datapipeline_clients, target.cache_key(), || {
+            Ok(DataPipelineClient::new_with(http_client()?, self.credentials(target)?, region(target)?))
+        })
+    }
+
+    /// Credentials for the last role in the target's chain, or the source's own
+    /// credentials when there are no roles to assume.
+    pub fn credentials(&self, target: &ClientTarget) -> Result<SharedCredentials, String> {
+        let mut chain = ClientTarget { roles: vec![], ..target.clone() };
+        let mut credentials = cached(&self.credentials, chain.cache_key(), || self.source_credentials())?;
+        for role in &target.roles {
+            chain = chain.assume(role.clone());
+            let previous = credentials;
+            credentials = cached(&self.credentials, chain.cache_key(), || assume_role(previous, role, region(target)?))?;
+        }
+        Ok(credentials)
+    }
+
+    fn source_credentials(&self) -> Result<SharedCredentials, String> {
+        match &self.source {
+            CredentialSource::Default => DefaultCredentialsProvider::new().map(SharedCredentials::new).map_err(credentials_error),
+            CredentialSource::Profile(name) => {
+                let mut provider = ProfileProvider::new().map_err(credentials_error)?;
+                provider.set_profile(name.as_str());
+                refreshing(provider)
+            },
+            CredentialSource::WebIdentityFromEnv => refreshing(WebIdentityProvider::from_k8s_env()),
+            CredentialSource::WebIdentity { token_file, role_arn, session_name } => refreshing(WebIdentityProvider::new(
+                Variable::from_text_file(token_file),
+                Variable::with_value(role_arn.clone()),
+                session_name.clone().map(Variable::with_value),
+            )),
+            CredentialSource::Static(credentials) => Ok(SharedCredentials::new(static_provider(credentials))),
+        }
+    }
+}
+
+fn assume_role(credentials: SharedCredentials, role: &AssumeRole, region: Region) -> Result<SharedCredentials, String> {
+    let sts = StsClient::new_with(http_client()?, credentials, region);
+    refreshing(StsAssumeRoleSessionCredentialsProvider::new(
+        sts,
+        role.role_arn.clone(),
+        role.session_name.clone(),
+        role.external_id.clone(),
+        role.duration,
+        None,
+        None,
+    ))
+}
+
+fn refreshing<P: ProvideAwsCredentials + Send + Sync + 'static>(provider: P) -> Result<SharedCredentials, String> {
+    AutoRefreshingProvider::new(provider).map(SharedCredentials::new).map_err(credentials_error)
+}
+
+fn region(target: &ClientTarget) -> Result<Region, String> {
+    target.config().rusoto_region().map_err(|e| ErrorResponse::json(e.as_str()))
+}
+
+fn credentials_error(e: CredentialsError) -> String {
+    ErrorResponse::json(e.to_string().as_str())
+}
+
+fn cached<T: Clone>(cache: &Mutex<HashMap<ClientKey, T>>, key: ClientKey, build: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    if let Some(value) = cache.lock().unwrap().get(&key) {
+        return Ok(value.clone());
+    }
+    let value = build()?;
+    Ok(cache.lock().unwrap().entry(key).or_insert(value).clone())
+}
+
+#[test]
+fn credentials_are_cached_per_link_of_the_chain() {
+    let factory = ClientFactory::new(CredentialSource::Static(crate::clients::models::client_config::StaticCredentials {
+        access_key_id: "key".to_string(),
+        secret_access_key: "secret".to_string(),
+        session_token: None,
+    }));
+    let hub = ClientTarget::new("eu-west-1").assume(AssumeRole::new("arn:aws:iam::111111111111:role/hub", "hub"));
+    let reader = hub.clone().assume(AssumeRole::new("arn:aws:iam::222222222222:role/reader", "reader").external_id("secret"));
+
+    let first = factory.credentials(&reader).unwrap();
+    let second = factory.credentials(&reader).unwrap();
+    assert!(Arc::ptr_eq(&first.0, &second.0));
+    assert_eq!(3, factory.credentials.lock().unwrap().len());
+
+    factory.credentials(&hub).unwrap();
+    assert_eq!(3, factory.credentials.lock().unwrap().len());
+    factory.credentials(&hub.assume(AssumeRole::new("arn:aws:iam::222222222222:role/reader", "other"))).unwrap();
+    assert_eq!(4, factory.credentials.lock().unwrap().len());
+    assert!(factory.s3(&ClientTarget::new("nowhere-1")).is_err());
+    assert!(factory.s3(&ClientTarget::new("nowhere-1").endpoint("http://localhost:4566")).is_ok());
+}
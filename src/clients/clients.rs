@@ -0,0 +1,37 @@
+use rusoto_core::HttpClient;
+use rusoto_credential::StaticProvider;
+use rusoto_datapipeline::DataPipelineClient;
+use rusoto_s3::S3Client;
+use crate::clients::models::client_config::{ClientConfig, StaticCredentials};
+use crate::errors::models::error_response::ErrorResponse;
+
+/// An S3 client for the config's region or custom endpoint. Rusoto always
+/// addresses buckets path style, which is what S3-compatible servers expect.
+pub fn s3_client(config: &ClientConfig) -> Result<S3Client, String> {
+    let region = config.rusoto_region().map_err(|e| ErrorResponse::json(e.as_str()))?;
+    match &config.credentials {
+        Some(credentials) => Ok(S3Client::new_with(http_client()?, static_provider(credentials), region)),
+        None => Ok(S3Client::new(region)),
+    }
+}
+
+pub fn datapipeline_client(config: &ClientConfig) -> Result<DataPipelineClient, String> {
+    let region = config.rusoto_region().map_err(|e| ErrorResponse::json(e.as_str()))?;
+    match &config.credentials {
+        Some(credentials) => Ok(DataPipelineClient::new_with(http_client()?, static_provider(credentials), region)),
+        None => Ok(DataPipelineClient::new(region)),
+    }
+}
+
+pub(crate) fn http_client() -> Result<HttpClient, String> {
+    HttpClient::new().map_err(|e| ErrorResponse::json(e.to_string().as_str()))
+}
+
+pub(crate) fn static_provider(credentials: &StaticCredentials) -> StaticProvider {
+    StaticProvider::new(
+        credentials.access_key_id.clone(),
+        credentials.secret_access_key.clone(),
+        credentials.session_token.clone(),
+        None,
+    )
+}
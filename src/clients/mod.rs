@@ -0,0 +1,3 @@
+pub mod clients;
+pub mod client_factory;
+pub mod models;
\ No newline at end of file
@@ -0,0 +1,121 @@
+use std::str::FromStr;
+use rusoto_core::Region;
+use crate::s3::models::s3_location::{dns_suffix, partition_for_region, S3Location};
+use crate::s3::models::s3_location_error::S3LocationError;
+use crate::s3::models::upload::url_encode;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StaticCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+/// How to reach a service: an AWS region, or a custom endpoint such as MinIO or
+/// LocalStack, with optional static credentials instead of the default chain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClientConfig {
+    pub region: String,
+    pub endpoint: Option<String>,
+    /// Only affects the URLs `object_url` formats. Rusoto clients always address
+    /// buckets path style whatever this is set to.
+    pub path_style: bool,
+    pub credentials: Option<StaticCredentials>,
+}
+
+impl ClientConfig {
+    pub fn new(region: &str) -> ClientConfig {
+        ClientConfig { region: region.to_string(), endpoint: None, path_style: false, credentials: None }
+    }
+
+    /// A custom endpoint, addressed path style as MinIO and LocalStack expect.
+    pub fn local(endpoint: &str) -> ClientConfig {
+        ClientConfig::new("us-east-1").endpoint(endpoint).path_style(true)
+    }
+
+    pub fn endpoint(mut self, endpoint: &str) -> ClientConfig {
+        self.endpoint = Some(endpoint.trim_end_matches('/').to_string());
+        self
+    }
+
+    pub fn path_style(mut self, path_style: bool) -> ClientConfig {
+        self.path_style = path_style;
+        self
+    }
+
+    pub fn static_credentials(mut self, access_key_id: &str, secret_access_key: &str) -> ClientConfig {
+        self.credentials = Some(StaticCredentials {
+            access_key_id: access_key_id.to_string(),
+            secret_access_key: secret_access_key.to_string(),
+            session_token: None,
+        });
+        self
+    }
+
+    /// Static credentials from an STS session, which need their session token.
+    pub fn temporary_credentials(mut self, access_key_id: &str, secret_access_key: &str, session_token: &str) -> ClientConfig {
+        self.credentials = Some(StaticCredentials {
+            access_key_id: access_key_id.to_string(),
+            secret_access_key: secret_access_key.to_string(),
+            session_token: Some(session_token.to_string()),
+        });
+        self
+    }
+
+    pub fn rusoto_region(&self) -> Result<Region, String> {
+        match &self.endpoint {
+            Some(endpoint) => Ok(Region::Custom { name: self.region.clone(), endpoint: endpoint.clone() }),
+            None => Region::from_str(&self.region).map_err(|e| format!("{}: {}", self.region, e)),
+        }
+    }
+
+    /// Parses a location, recognising URLs on this config's endpoint as well as
+    /// everything `S3Location::from` accepts.
+    pub fn location(&self, path: &str) -> Result<S3Location, S3LocationError> {
+        match &self.endpoint {
+            Some(endpoint) => S3Location::from_endpoint(path, endpoint),
+            None => S3Location::from(path),
+        }
+    }
+
+    /// The HTTP URL of an object on this config's endpoint or region, on the
+    /// region's partition domain when there is no endpoint.
+    pub fn object_url(&self, location: &S3Location) -> String {
+        let key = location.key.split('/').map(url_encode).collect::<Vec<String>>().join("/");
+        let endpoint = self.endpoint.clone()
+            .unwrap_or_else(|| format!("https://s3.{}.{}", self.region, dns_suffix(partition_for_region(&self.region))));
+        match endpoint.split_once("://") {
+            Some((scheme, host)) if !self.path_style => format!("{}://{}.{}/{}", scheme, location.bucket, host, key),
+            _ => format!("{}/{}/{}", endpoint, location.bucket, key),
+        }
+    }
+}
+
+#[test]
+fn custom_endpoint_becomes_custom_region() {
+    let config = ClientConfig::local("http://localhost:4566/").static_credentials("test", "test");
+    assert_eq!(Region::Custom { name: "us-east-1".to_string(), endpoint: "http://localhost:4566".to_string() }, config.rusoto_region().unwrap());
+    assert_eq!(Region::EuWest1, ClientConfig::new("eu-west-1").rusoto_region().unwrap());
+    assert!(ClientConfig::new("nowhere-1").rusoto_region().is_err());
+}
+
+#[test]
+fn object_urls_follow_addressing_style() {
+    let location = S3Location::new("bucket", "a b/c");
+    assert_eq!("http://localhost:9000/bucket/a%20b/c", ClientConfig::local("http://localhost:9000").object_url(&location));
+    assert_eq!("https://bucket.s3.eu-west-1.amazonaws.com/a%20b/c", ClientConfig::new("eu-west-1").object_url(&location));
+    assert_eq!("https://bucket.s3.cn-north-1.amazonaws.com.cn/a%20b/c", ClientConfig::new("cn-north-1").object_url(&location));
+}
+
+#[test]
+fn temporary_credentials_keep_the_session_token() {
+    let config = ClientConfig::new("eu-west-1").temporary_credentials("key", "secret", "token");
+    assert_eq!(Some("token".to_string()), config.credentials.unwrap().session_token);
+    assert_eq!(None, ClientConfig::new("eu-west-1").static_credentials("key", "secret").credentials.unwrap().session_token);
+}
+
+#[test]
+fn locations_on_the_endpoint_are_parsed() {
+    let config = ClientConfig::local("http://localhost:9000");
+    assert_eq!(S3Location::new("bucket", "key"), config.location("http://localhost:9000/bucket/key").unwrap());
+}
@@ -0,0 +1,166 @@
+use chrono::Duration;
+use crate::clients::models::client_config::{ClientConfig, StaticCredentials};
+
+/// Where the first credentials in a chain come from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CredentialSource {
+    /// Environment, profile file, container or instance metadata, in rusoto's usual order.
+    Default,
+    Profile(String),
+    /// A web identity token, as used by EKS service accounts, read from the
+    /// `AWS_WEB_IDENTITY_TOKEN_FILE`, `AWS_ROLE_ARN` and `AWS_ROLE_SESSION_NAME` variables.
+    WebIdentityFromEnv,
+    WebIdentity { token_file: String, role_arn: String, session_name: Option<String> },
+    Static(StaticCredentials),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssumeRole {
+    pub role_arn: String,
+    pub session_name: String,
+    pub external_id: Option<String>,
+    pub duration: Option<Duration>,
+}
+
+impl AssumeRole {
+    pub fn new(role_arn: &str, session_name: &str) -> AssumeRole {
+        AssumeRole { role_arn: role_arn.to_string(), session_name: session_name.to_string(), external_id: None, duration: None }
+    }
+
+    pub fn external_id(mut self, external_id: &str) -> AssumeRole {
+        self.external_id = Some(external_id.to_string());
+        self
+    }
+
+    pub fn duration(mut self, duration: Duration) -> AssumeRole {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub fn account(&self) -> Option<String> {
+        role_account(&self.role_arn)
+    }
+}
+
+/// A region to build clients for, reached through zero or more roles, each
+/// assumed with the credentials of the one before. With an endpoint, clients
+/// (including the STS clients that assume the roles) talk to it instead, as for
+/// MinIO or LocalStack.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClientTarget {
+    pub region: String,
+    pub endpoint: Option<String>,
+    pub roles: Vec<AssumeRole>,
+}
+
+impl ClientTarget {
+    pub fn new(region: &str) -> ClientTarget {
+        ClientTarget { region: region.to_string(), endpoint: None, roles: vec![] }
+    }
+
+    pub fn endpoint(mut self, endpoint: &str) -> ClientTarget {
+        self.endpoint = Some(endpoint.trim_end_matches('/').to_string());
+        self
+    }
+
+    pub fn assume(mut self, role: AssumeRole) -> ClientTarget {
+        self.roles.push(role);
+        self
+    }
+
+    pub fn role_arn(&self) -> Option<&str> {
+        self.roles.last().map(|r| r.role_arn.as_str())
+    }
+
+    pub fn account(&self) -> Option<String> {
+        self.roles.last().and_then(|r| r.account())
+    }
+
+    pub fn config(&self) -> ClientConfig {
+        match &self.endpoint {
+            Some(endpoint) => ClientConfig::new(&self.region).endpoint(endpoint),
+            None => ClientConfig::new(&self.region),
+        }
+    }
+
+    pub fn cache_key(&self) -> ClientKey {
+        ClientKey {
+            account: self.account(),
+            region: self.region.clone(),
+            endpoint: self.endpoint.clone(),
+            roles: self.roles.iter().map(RoleKey::from).collect(),
+        }
+    }
+}
+
+/// Clients and credentials are shared between targets with the same key. The
+/// whole role chain is part of it, as the same final role reached another way
+/// can carry a different session name or external id.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ClientKey {
+    pub account: Option<String>,
+    pub region: String,
+    pub endpoint: Option<String>,
+    pub roles: Vec<RoleKey>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RoleKey {
+    pub role_arn: String,
+    pub session_name: String,
+    pub external_id: Option<String>,
+    /// The requested session duration in seconds.
+    pub duration: Option<i64>,
+}
+
+impl RoleKey {
+    pub fn from(role: &AssumeRole) -> RoleKey {
+        RoleKey {
+            role_arn: role.role_arn.clone(),
+            session_name: role.session_name.clone(),
+            external_id: role.external_id.clone(),
+            duration: role.duration.map(|d| d.num_seconds()),
+        }
+    }
+}
+
+/// The account id in an IAM role ARN such as `arn:aws:iam::123456789012:role/name`.
+pub fn role_account(role_arn: &str) -> Option<String> {
+    let parts: Vec<&str> = role_arn.splitn(6, ':').collect();
+    match parts.as_slice() {
+        ["arn", _, "iam", "", account, _] if account.len() == 12 && account.chars().all(|c| c.is_ascii_digit()) => Some(account.to_string()),
+        _ => None,
+    }
+}
+
+#[test]
+fn account_is_read_from_role_arn() {
+    assert_eq!(Some("123456789012".to_string()), role_account("arn:aws:iam::123456789012:role/path/reader"));
+    assert_eq!(Some("123456789012".to_string()), role_account("arn:aws-cn:iam::123456789012:role/reader"));
+    assert_eq!(None, role_account("arn:aws:s3:::bucket"));
+    assert_eq!(None, role_account("reader"));
+}
+
+#[test]
+fn targets_are_keyed_by_the_whole_chain() {
+    let hub = ClientTarget::new("eu-west-1").assume(AssumeRole::new("arn:aws:iam::111111111111:role/hub", "hub"));
+    let target = hub.clone().assume(AssumeRole::new("arn:aws:iam::222222222222:role/reader", "reader").external_id("secret"));
+    assert_eq!(ClientKey {
+        account: Some("222222222222".to_string()),
+        region: "eu-west-1".to_string(),
+        endpoint: None,
+        roles: vec![
+            RoleKey { role_arn: "arn:aws:iam::111111111111:role/hub".to_string(), session_name: "hub".to_string(), external_id: None, duration: None },
+            RoleKey { role_arn: "arn:aws:iam::222222222222:role/reader".to_string(), session_name: "reader".to_string(), external_id: Some("secret".to_string()), duration: None },
+        ],
+    }, target.cache_key());
+    assert_eq!(ClientKey { account: None, region: "eu-west-1".to_string(), endpoint: None, roles: vec![] }, ClientTarget::new("eu-west-1").cache_key());
+
+    let direct = ClientTarget::new("eu-west-1").assume(AssumeRole::new("arn:aws:iam::222222222222:role/reader", "reader").external_id("secret"));
+    let other_id = hub.clone().assume(AssumeRole::new("arn:aws:iam::222222222222:role/reader", "reader").external_id("other"));
+    let longer = hub.assume(AssumeRole::new("arn:aws:iam::222222222222:role/reader", "reader").external_id("secret").duration(Duration::hours(12)));
+    assert_ne!(target.cache_key(), direct.cache_key());
+    assert_ne!(target.cache_key(), other_id.cache_key());
+    assert_ne!(target.cache_key(), longer.cache_key());
+    assert_ne!(target.cache_key(), target.clone().endpoint("http://localhost:4566").cache_key());
+}
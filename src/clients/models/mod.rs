@@ -0,0 +1,2 @@
+pub mod client_config;
+pub mod client_target;
\ No newline at end of file
@@ -1,10 +1,14 @@
 use std::collections::HashMap;
-use ::chrono::Utc;
+use ::chrono::{DateTime, Utc};
 use ::rusoto_datapipeline::DataPipelineClient;
 use ::rusoto_datapipeline::{ListPipelinesInput, PipelineIdName, DataPipeline, PipelineDescription, DescribePipelinesInput, Field};
 use ::rusoto_datapipeline::{QueryObjectsInput, DescribeObjectsInput};
+use futures::future::join_all;
+use crate::clients::client_factory::ClientFactory;
+use crate::clients::models::client_target::ClientTarget;
 use crate::datapipelines::models::pipeline::Pipeline;
 use crate::datapipelines::models::pipeline_tasks::PipelineTasks;
+use crate::datapipelines::models::status_report::{StatusReport, TargetFailure};
 use crate::datapipelines::models::pipeline_task_status::{PipelineTaskStatus, PipelineTaskStatus::*};
 use crate::utilities::get_or_blank;
 use tokio::runtime::Runtime;
@@ -29,7 +33,7 @@ async fn get_pipeline_id_names(data_pipeline_client: &DataPipelineClient) -> Res
     Ok(all_pipelines)
 }
 
-async fn get_pipeline_tasks(pipeline_id: String, client: &DataPipelineClient, allowed_statuses: &Vec<PipelineTaskStatus>) -> Vec<PipelineTasks> {
+async fn get_pipeline_tasks(pipeline_id: String, client: &DataPipelineClient, allowed_statuses: &Vec<PipelineTaskStatus>) -> Result<Vec<PipelineTasks>, String> {
     let allowed_statuses_strs = allowed_statuses.iter().map(|s| s.as_str()).collect::<Vec<&str>>();
     let query_objects_input: QueryObjectsInput = QueryObjectsInput {
         limit: None,
@@ -39,74 +43,45 @@ async fn get_pipeline_tasks(pipeline_id: String, client: &DataPipelineClient, al
         sphere: "ATTEMPT".to_string(),
     };
 
-    let query_objects_response = client.query_objects(query_objects_input).await;
-    let task_ids: Vec<String> = match query_objects_response {
-        Ok(output) => {
-            match output.ids {
-                Some(task_ids) => task_ids,
-                None => vec![],
-            }
-        }
-
-        Err(_e) => vec![],
-    };
+    let task_ids: Vec<String> = client.query_objects(query_objects_input).await
+        .map_err(|e| format!("Error querying tasks of pipeline {} {}", pipeline_id, e))?
+        .ids.unwrap_or_default();
     let describe_objects_input = DescribeObjectsInput {
         evaluate_expressions: None,
         marker: None,
         object_ids: task_ids,
         pipeline_id: pipeline_id.clone(),
     };
-    let describe_objects_response = client.describe_objects(describe_objects_input).await;
+    let output = client.describe_objects(describe_objects_input).await
+        .map_err(|e| format!("Error describing tasks of pipeline {} {}", pipeline_id, e))?;
 
-    match describe_objects_response {
-        Ok(output) => {
-            output.pipeline_objects.iter().flat_map(|pipeline_object| {
-                let fields = convert(&pipeline_object.fields);
-                let task_status = match PipelineTaskStatus::value(&get_or_blank(&"@status".to_string(), &fields)) {
-                    Some(ts) => ts.as_str(),
-                    None => "",
-                };
+    Ok(output.pipeline_objects.iter().flat_map(|pipeline_object| {
+        let fields = convert(&pipeline_object.fields);
+        let task_status = match PipelineTaskStatus::value(&get_or_blank(&"@status".to_string(), &fields)) {
+            Some(ts) => ts.as_str(),
+            None => "",
+        };
 
-                if allowed_statuses_strs.contains(&task_status) || allowed_statuses.is_empty() {
-                    Some(PipelineTasks {
-                        pipeline_id: pipeline_id.clone(),
-                        task_id: pipeline_object.id.clone(),
-                        task_name: pipeline_object.name.clone(),
-                        status: task_status.to_string(),
-                        attempt_status: get_or_blank(&"attemptStatus".to_string(), &fields),
-                    })
-                } else { None }
-            }).collect::<Vec<PipelineTasks>>()
-        }
-        Err(_e) => {
-            println!("pipeline[{}]: {:?}", pipeline_id, _e);
-            vec![]
-        }
-    }
+        if allowed_statuses_strs.contains(&task_status) || allowed_statuses.is_empty() {
+            Some(PipelineTasks {
+                pipeline_id: pipeline_id.clone(),
+                task_id: pipeline_object.id.clone(),
+                task_name: pipeline_object.name.clone(),
+                status: task_status.to_string(),
+                attempt_status: get_or_blank(&"attemptStatus".to_string(), &fields),
+            })
+        } else { None }
+    }).collect::<Vec<PipelineTasks>>())
 }
 
-async fn get_pipelines_descriptions(pipeline_ids: Vec<String>, data_pipeline_client: &DataPipelineClient) -> Vec<PipelineDescription> {
+async fn get_pipelines_descriptions(pipeline_ids: Vec<String>, data_pipeline_client: &DataPipelineClient) -> Result<Vec<PipelineDescription>, String> {
     let mut all_describe_pipelines_output: Vec<PipelineDescription> = vec![];
-    let _max = pipeline_ids.len();
-    let mut _start = 0;
-    let mut _end = if _max < 25 { _max } else { 25 };
-    while _start < _max {
-        let subset_pipelines = pipeline_ids[_start.._end].to_vec();
-        let describe_pipelines_output = data_pipeline_client.describe_pipelines(DescribePipelinesInput { pipeline_ids: subset_pipelines }).await;
-        match describe_pipelines_output {
-            Ok(output) => {
-                all_describe_pipelines_output.append(&mut output.pipeline_description_list.clone());
-                _start += 25;
-                _end += 25;
-                if _max < _end { _end = _max };
-            }
-            Err(e) => {
-                println!("Error describing pipelines {}", e);
-                all_describe_pipelines_output = vec![];
-            }
-        }
+    for subset_pipelines in pipeline_ids.chunks(25) {
+        let output = data_pipeline_client.describe_pipelines(DescribePipelinesInput { pipeline_ids: subset_pipelines.to_vec() }).await
+            .map_err(|e| format!("Error describing pipelines {}", e))?;
+        all_describe_pipelines_output.extend(output.pipeline_description_list);
     }
-    all_describe_pipelines_output
+    Ok(all_describe_pipelines_output)
 }
 
 fn convert(fields: &Vec<Field>) -> HashMap<String, String> {
@@ -121,29 +96,48 @@ fn convert(fields: &Vec<Field>) -> HashMap<String, String> {
 
 pub fn status(client: &DataPipelineClient, pipeline_name_filters: &Vec<String>, filter_operation: &str) -> Result<Vec<Pipeline>, String> {
     let mut rt = Runtime::new().unwrap();
-    let allowed_status_query = vec![Running, WaitingOnDependencies, Creating, WaitingForRunner];
+    rt.block_on(async { get_pipelines_status(client, pipeline_name_filters, filter_operation, Utc::now()).await })
+}
+
+/// Runs `status` against every target at once, building clients through the
+/// factory. A target that can't be reached is reported as a failure rather than
+/// failing the whole report.
+pub fn status_multi(factory: &ClientFactory, targets: &[ClientTarget], pipeline_name_filters: &[String], filter_operation: &str) -> StatusReport {
+    let mut rt = Runtime::new().unwrap();
     let now = Utc::now();
+    let results = rt.block_on(async {
+        join_all(targets.iter().map(|target| async move {
+            let client = factory.datapipeline(target)?;
+            get_pipelines_status(&client, pipeline_name_filters, filter_operation, now).await
+        })).await
+    });
 
-    match rt.block_on(async { get_pipeline_id_names(&client).await }){
-        Ok(pipeline_id_name_result) => {
-            let pipeline_ids = pipeline_id_name_result.iter().flat_map(|pin| &pin.id).map(|c| c.clone()).collect();
-            let pipelines_status = rt.block_on(async {get_pipelines_descriptions(pipeline_ids, &client).await}).iter()
-                .filter(|pipe_desc| if filter_operation == "include" {
-                    pipeline_name_filters.contains(&pipe_desc.name)
-                } else {
-                    !pipeline_name_filters.contains(&pipe_desc.name) })
-                .flat_map( |pipeline_desc| {
-                    let fields = convert(&pipeline_desc.fields);
-                    let tasks = rt.block_on(async {get_pipeline_tasks(
-                        pipeline_desc.pipeline_id.clone(),
-                        client,
-                        &allowed_status_query).await});
-                    Pipeline::create(tasks, fields, now)
-                })
-                .collect();
+    let mut report = StatusReport::default();
+    for (target, result) in targets.iter().zip(results) {
+        match result {
+            Ok(pipelines) => report.pipelines.extend(pipelines.into_iter().map(|pipeline| pipeline.with_target(target))),
+            Err(e) => report.failures.push(TargetFailure::new(target, &e)),
+        }
+    }
+    report
+}
+
+async fn get_pipelines_status(client: &DataPipelineClient, pipeline_name_filters: &[String], filter_operation: &str, now: DateTime<Utc>) -> Result<Vec<Pipeline>, String> {
+    let allowed_status_query = vec![Running, WaitingOnDependencies, Creating, WaitingForRunner];
 
-            Ok(pipelines_status)
-        },
-        Err(e) => Err(e),
+    let pipeline_ids = get_pipeline_id_names(client).await?.iter().flat_map(|pin| &pin.id).cloned().collect();
+    let mut pipelines_status = vec![];
+    for pipeline_desc in get_pipelines_descriptions(pipeline_ids, client).await?.iter()
+        .filter(|pipe_desc| if filter_operation == "include" {
+            pipeline_name_filters.contains(&pipe_desc.name)
+        } else {
+            !pipeline_name_filters.contains(&pipe_desc.name) }) {
+        let fields = convert(&pipeline_desc.fields);
+        let pipeline = match get_pipeline_tasks(pipeline_desc.pipeline_id.clone(), client, &allowed_status_query).await {
+            Ok(tasks) => Pipeline::create(tasks, fields, now),
+            Err(e) => Pipeline::create(vec![], fields, now).map(|pipeline| pipeline.with_task_error(&e)),
+        };
+        pipelines_status.extend(pipeline);
     }
+    Ok(pipelines_status)
 }
@@ -1,3 +1,4 @@
 pub mod pipeline;
 pub mod pipeline_task_status;
 pub mod pipeline_tasks;
+pub mod status_report;
\ No newline at end of file
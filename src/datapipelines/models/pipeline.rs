@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 use ::serde_derive::Serialize;
 use chrono::{DateTime,Utc,TimeZone};
+use crate::clients::models::client_target::ClientTarget;
 use crate::datapipelines::models::pipeline_task_status::PipelineTaskStatus;
 use crate::datapipelines::models::pipeline_tasks::PipelineTasks;
+use crate::s3::models::output_check::OutputVerdict;
 use crate::utilities::get_or_blank;
 
 #[derive(Serialize, Debug, Clone)]
@@ -10,6 +12,8 @@ pub struct Pipeline {
     pub id: String,
     pub name: String,
     pub account_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
     pub health_status: String,
     pub pipeline_state: String,
     pub latest_run_time: Option<DateTime<Utc>>,
@@ -17,6 +21,11 @@ pub struct Pipeline {
     pub scheduled_period: String,
     pub since_last_run_time: Option<String>,
     pub tasks: Vec<PipelineTasks>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub output_checks: Vec<OutputVerdict>,
+    /// Why the pipeline's tasks couldn't be read, in which case `tasks` is empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_error: Option<String>,
 }
 
 impl Pipeline {
@@ -29,6 +38,7 @@ impl Pipeline {
             id: get_or_blank(&"@id".to_string(), &fields),
             name: get_or_blank(&"name".to_string(), &fields),
             account_id: get_or_blank(&"@accountId".to_string(), &fields),
+            region: None,
             health_status: get_or_blank(&"@healthStatus".to_string(), &fields),
             pipeline_state,
             latest_run_time: convert_to_date_time(&latest_run_time),
@@ -36,6 +46,8 @@ impl Pipeline {
             scheduled_period: get_or_blank(&"@scheduledPeriod".to_string(), &fields),
             since_last_run_time,
             tasks,
+            output_checks: vec![],
+            task_error: None,
         };
 
         if pipeline.id.is_empty() ||
@@ -57,6 +69,32 @@ impl Pipeline {
         self.health_status == "HEALTHY"
     }
 
+    pub fn with_task_error(mut self, error: &str) -> Pipeline {
+        self.task_error = Some(error.to_string());
+        self
+    }
+
+    pub fn with_output_check(mut self, verdict: OutputVerdict) -> Pipeline {
+        self.output_checks.push(verdict);
+        self
+    }
+
+    /// Records the region the pipeline was found in, and the target's account
+    /// when the pipeline's own fields didn't carry one.
+    pub fn with_target(mut self, target: &ClientTarget) -> Pipeline {
+        if self.account_id.is_empty() {
+            self.account_id = target.account().unwrap_or_default();
+        }
+        self.region = Some(target.region.clone());
+        self
+    }
+
+    /// Whether every output check passed, or `None` when the output wasn't checked.
+    pub fn is_output_complete(&self) -> Option<bool> {
+        if self.output_checks.is_empty() { return None; }
+        Some(self.output_checks.iter().all(|verdict| verdict.complete))
+    }
+
     pub fn to_json(&self) -> String {
         serde_json::to_string(self).unwrap()
     }
@@ -85,6 +123,7 @@ fn pipeline_status_should_serialize() {
         id: "df-0977100BVBIK29Y9RF6".to_string(),
         name: "Scopus Author Profile Backfill Pipeline".to_string(),
         account_id: "242194143705".to_string(),
+        region: None,
         health_status: "HEALTHY".to_string(),
         pipeline_state: "FINISHED".to_string(),
         latest_run_time: convert_to_date_time(&"2017-08-31T14:58:04"),
@@ -92,6 +131,8 @@ fn pipeline_status_should_serialize() {
         scheduled_period: "24 hours".to_string(),
         since_last_run_time: Some("48448299".to_string()),
         tasks: vec![],
+        output_checks: vec![],
+        task_error: None,
     };
 
     assert_eq!(expected, actual.to_json());
@@ -103,6 +144,7 @@ fn healthy_pipeline_is_identified_correctly() {
         id: "df-0977100BVBIK29Y9RF6".to_string(),
         name: "Scopus Author Profile Backfill Pipeline".to_string(),
         account_id: "242194143705".to_string(),
+        region: None,
         health_status: "HEALTHY".to_string(),
         pipeline_state: "FINISHED".to_string(),
         latest_run_time: convert_to_date_time(&"2017-08-31T14:58:04"),
@@ -110,6 +152,8 @@ fn healthy_pipeline_is_identified_correctly() {
         scheduled_period: "24 hours".to_string(),
         since_last_run_time: Some("48448299".to_string()),
         tasks: vec![],
+        output_checks: vec![],
+        task_error: None,
     };
 
     assert_eq!(true, healthy_pipeline.is_healthy());
@@ -121,6 +165,7 @@ fn broken_pipeline_is_identified_correctly() {
         id: "df-0977100BVBIK29Y9RF6".to_string(),
         name: "Scopus Author Profile Backfill Pipeline".to_string(),
         account_id: "242194143705".to_string(),
+        region: None,
         health_status: "ERROR".to_string(),
         pipeline_state: "FINISHED".to_string(),
         latest_run_time: convert_to_date_time(&"2017-08-31T14:58:04"),
@@ -128,6 +173,8 @@ fn broken_pipeline_is_identified_correctly() {
         scheduled_period: "24 hours".to_string(),
         since_last_run_time: Some("48448299".to_string()),
         tasks: vec![],
+        output_checks: vec![],
+        task_error: None,
     };
 
     assert_eq!(false, broken_pipeline.is_healthy());
@@ -139,6 +186,7 @@ fn building_healthy_pipeline_is_identified_correctly() {
         id: "df-0977100BVBIK29Y9RF6".to_string(),
         name: "Scopus Author Profile Backfill Pipeline".to_string(),
         account_id: "242194143705".to_string(),
+        region: None,
         health_status: "HEALTHY".to_string(),
         pipeline_state: "FINISHED".to_string(),
         latest_run_time: convert_to_date_time(&"2017-08-31T14:58:04"),
@@ -146,6 +194,8 @@ fn building_healthy_pipeline_is_identified_correctly() {
         scheduled_period: "24 hours".to_string(),
         since_last_run_time: Some("48448299".to_string()),
         tasks: vec![],
+        output_checks: vec![],
+        task_error: None,
     };
 
     assert_eq!(false, building_healthy_pipeline.is_building());
@@ -186,4 +236,68 @@ fn convert_to_date_time_invalidates_incorrect_datetime_string() {
     assert!(convert_to_date_time("2010-10-10T10").is_none());
     assert!(convert_to_date_time("").is_none());
     assert!(convert_to_date_time("2010-10-10T10:1010").is_none());
-}
\ No newline at end of file
+}
+
+#[test]
+fn target_adds_region_and_missing_account() {
+    let pipeline = Pipeline {
+        id: "df-0977100BVBIK29Y9RF6".to_string(),
+        name: "Scopus Author Profile Backfill Pipeline".to_string(),
+        account_id: "".to_string(),
+        region: None,
+        health_status: "HEALTHY".to_string(),
+        pipeline_state: "SCHEDULED".to_string(),
+        latest_run_time: None,
+        next_run_time: None,
+        scheduled_period: "24 hours".to_string(),
+        since_last_run_time: None,
+        tasks: vec![],
+        output_checks: vec![],
+        task_error: None,
+    };
+    let target = ClientTarget::new("eu-west-1")
+        .assume(crate::clients::models::client_target::AssumeRole::new("arn:aws:iam::242194143705:role/reader", "status"));
+
+    let located = pipeline.with_target(&target);
+    assert_eq!("242194143705", located.account_id);
+    assert!(located.to_json().contains("\"account_id\":\"242194143705\",\"region\":\"eu-west-1\","));
+}
+
+#[test]
+fn unchecked_output_is_not_complete() {
+    let pipeline = Pipeline {
+        id: "df-0977100BVBIK29Y9RF6".to_string(),
+        name: "Scopus Author Profile Backfill Pipeline".to_string(),
+        account_id: "242194143705".to_string(),
+        region: None,
+        health_status: "HEALTHY".to_string(),
+        pipeline_state: "FINISHED".to_string(),
+        latest_run_time: None,
+        next_run_time: None,
+        scheduled_period: "24 hours".to_string(),
+        since_last_run_time: None,
+        tasks: vec![],
+        output_checks: vec![],
+        task_error: None,
+    };
+    assert_eq!(None, pipeline.is_output_complete());
+
+    let verdict = |complete| OutputVerdict { location: "s3://b/out/".to_string(), complete, objects: 1, bytes: 1, newest: None, failures: vec![] };
+    let checked = pipeline.with_output_check(verdict(true));
+    assert_eq!(Some(true), checked.is_output_complete());
+    assert_eq!(Some(false), checked.with_output_check(verdict(false)).is_output_complete());
+}
+
+#[test]
+fn task_errors_are_reported_on_the_pipeline() {
+    let mut fields = HashMap::new();
+    fields.insert("@id".to_string(), "df-0977100BVBIK29Y9RF6".to_string());
+    fields.insert("name".to_string(), "Scopus Author Profile Backfill Pipeline".to_string());
+    fields.insert("@healthStatus".to_string(), "HEALTHY".to_string());
+    let pipeline = Pipeline::create(vec![], fields, Utc::now()).unwrap();
+    assert!(!pipeline.to_json().contains("task_error"));
+
+    let failed = pipeline.with_task_error("Error querying tasks of pipeline df-0977100BVBIK29Y9RF6");
+    assert!(failed.tasks.is_empty());
+    assert!(failed.to_json().contains("\"task_error\":\"Error querying tasks of pipeline df-0977100BVBIK29Y9RF6\""));
+}
@@ -0,0 +1,51 @@
+use ::serde_derive::Serialize;
+use crate::clients::models::client_target::ClientTarget;
+use crate::datapipelines::models::pipeline::Pipeline;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TargetFailure {
+    pub account: Option<String>,
+    pub region: String,
+    pub role_arn: Option<String>,
+    pub error: String,
+}
+
+impl TargetFailure {
+    pub fn new(target: &ClientTarget, error: &str) -> TargetFailure {
+        TargetFailure {
+            account: target.account(),
+            region: target.region.clone(),
+            role_arn: target.role_arn().map(|r| r.to_string()),
+            error: error.to_string(),
+        }
+    }
+}
+
+/// Pipelines from every target that could be queried, and why the others couldn't.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct StatusReport {
+    pub pipelines: Vec<Pipeline>,
+    pub failures: Vec<TargetFailure>,
+}
+
+impl StatusReport {
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[test]
+fn failures_record_the_target() {
+    let target = ClientTarget::new("us-east-1")
+        .assume(crate::clients::models::client_target::AssumeRole::new("arn:aws:iam::111111111111:role/reader", "status"));
+    let report = StatusReport { pipelines: vec![], failures: vec![TargetFailure::new(&target, "denied")] };
+    assert!(!report.is_complete());
+    assert_eq!(
+        "{\"pipelines\":[],\"failures\":[{\"account\":\"111111111111\",\"region\":\"us-east-1\",\"role_arn\":\"arn:aws:iam::111111111111:role/reader\",\"error\":\"denied\"}]}",
+        report.to_json()
+    );
+}
@@ -1,4 +1,5 @@
 pub mod datapipelines;
 pub mod s3;
+pub mod clients;
 mod utilities;
 pub mod errors;
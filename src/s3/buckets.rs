@@ -0,0 +1,93 @@
+use std::error::Error;
+use rusoto_core::RusotoError;
+use rusoto_s3::{GetBucketEncryptionRequest, GetBucketLifecycleConfigurationRequest, GetBucketLocationRequest, GetBucketLoggingRequest};
+use rusoto_s3::{GetBucketReplicationRequest, GetBucketTaggingRequest, GetBucketVersioningRequest, GetPublicAccessBlockRequest, S3, S3Client};
+use tokio::runtime::Runtime;
+use crate::errors::models::error_response::ErrorResponse;
+use crate::s3::models::bucket::{region_from_error_body, region_from_location_constraint, BucketEncryption, BucketInfo};
+use crate::s3::models::bucket::{BucketLogging, BucketReplication, PublicAccessBlock, S3Bucket};
+
+pub fn list_buckets(client: &S3Client) -> Result<Vec<S3Bucket>, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        client.list_buckets().await
+            .map(|output| output.buckets.unwrap_or_default().iter().map(S3Bucket::from).collect())
+            .map_err(|e| ErrorResponse::json(e.to_string().as_str()))
+    })
+}
+
+/// The region a bucket lives in. A client in the wrong region gets a redirect
+/// naming the right one, which is followed rather than reported as an error.
+pub fn bucket_region(client: &S3Client, bucket: &str) -> Result<String, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async { get_bucket_region(client, bucket).await })
+}
+
+/// Versioning, encryption, lifecycle, public access block, logging, replication
+/// and tags for a bucket. The client should be in the bucket's region, see
+/// `bucket_region`.
+pub fn bucket_info(client: &S3Client, bucket: &str) -> Result<BucketInfo, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async { get_bucket_info(client, bucket).await })
+}
+
+pub(crate) async fn get_bucket_region(client: &S3Client, bucket: &str) -> Result<String, String> {
+    let result = client.get_bucket_location(GetBucketLocationRequest {
+        bucket: bucket.to_string(),
+    }).await;
+
+    match result {
+        Ok(output) => Ok(region_from_location_constraint(output.location_constraint.as_deref())),
+        Err(RusotoError::Unknown(response)) => response.headers.get("x-amz-bucket-region").cloned()
+            .or_else(|| region_from_error_body(&response.body_as_str()))
+            .ok_or_else(|| ErrorResponse::json(format!("could not determine region of bucket {}: {}", bucket, response.body_as_str()).as_str())),
+        Err(e) => Err(ErrorResponse::json(e.to_string().as_str())),
+    }
+}
+
+pub(crate) async fn get_bucket_info(client: &S3Client, bucket: &str) -> Result<BucketInfo, String> {
+    let name = bucket.to_string();
+    let (region, versioning, encryption, lifecycle, public_access_block, logging, replication, tagging) = futures::try_join!(
+        get_bucket_region(client, bucket),
+        async { client.get_bucket_versioning(GetBucketVersioningRequest { bucket: name.clone() }).await.map_err(error) },
+        async { unless_missing(client.get_bucket_encryption(GetBucketEncryptionRequest { bucket: name.clone() }).await, "ServerSideEncryptionConfigurationNotFoundError") },
+        async { unless_missing(client.get_bucket_lifecycle_configuration(GetBucketLifecycleConfigurationRequest { bucket: name.clone() }).await, "NoSuchLifecycleConfiguration") },
+        async { unless_missing(client.get_public_access_block(GetPublicAccessBlockRequest { bucket: name.clone() }).await, "NoSuchPublicAccessBlockConfiguration") },
+        async { client.get_bucket_logging(GetBucketLoggingRequest { bucket: name.clone() }).await.map_err(error) },
+        async { unless_missing(client.get_bucket_replication(GetBucketReplicationRequest { bucket: name.clone() }).await, "ReplicationConfigurationNotFoundError") },
+        async { unless_missing(client.get_bucket_tagging(GetBucketTaggingRequest { bucket: name.clone() }).await, "NoSuchTagSet") },
+    )?;
+
+    Ok(BucketInfo {
+        name,
+        region,
+        versioning: versioning.status,
+        mfa_delete: versioning.mfa_delete,
+        encryption: encryption.and_then(|e| e.server_side_encryption_configuration)
+            .map(|c| c.rules.iter()
+                .filter_map(|rule| rule.apply_server_side_encryption_by_default.as_ref())
+                .map(|d| BucketEncryption { algorithm: d.sse_algorithm.clone(), kms_key_id: d.kms_master_key_id.clone() })
+                .collect())
+            .unwrap_or_default(),
+        lifecycle_rule_ids: lifecycle.and_then(|l| l.rules)
+            .map(|rules| rules.iter().map(|r| r.id.clone().unwrap_or_default()).collect())
+            .unwrap_or_default(),
+        public_access_block: public_access_block.and_then(|p| p.public_access_block_configuration).as_ref().map(PublicAccessBlock::from),
+        logging: logging.logging_enabled.map(|l| BucketLogging { target_bucket: l.target_bucket, target_prefix: l.target_prefix }),
+        replication: replication.and_then(|r| r.replication_configuration).map(|c| BucketReplication { role: c.role, rules: c.rules.len() }),
+        tags: tagging.map(|t| t.tag_set.into_iter().map(|tag| (tag.key, tag.value)).collect()).unwrap_or_default(),
+    })
+}
+
+fn error<E: Error + 'static>(e: RusotoError<E>) -> String {
+    ErrorResponse::json(e.to_string().as_str())
+}
+
+/// Treats the error S3 returns for a never-configured setting as `None`.
+fn unless_missing<T, E: Error + 'static>(result: Result<T, RusotoError<E>>, missing_code: &str) -> Result<Option<T>, String> {
+    match result {
+        Ok(output) => Ok(Some(output)),
+        Err(e) if e.to_string().contains(missing_code) => Ok(None),
+        Err(e) => Err(error(e)),
+    }
+}
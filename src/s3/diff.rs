@@ -0,0 +1,58 @@
+use futures::future::{join, try_join};
+use futures::stream::{self, StreamExt};
+use rusoto_s3::S3Client;
+use tokio::runtime::Runtime;
+use crate::s3::metadata::head;
+use crate::s3::models::diff::{DiffKind, PrefixDiff};
+use crate::s3::models::object_metadata::ObjectMetadata;
+use crate::s3::models::object_filter::ObjectFilter;
+use crate::s3::models::s3_location::S3Location;
+use crate::s3::operations::as_prefix;
+use crate::s3::s3::s3_list;
+
+const HEAD_CONCURRENCY: usize = 8;
+
+/// Lists both prefixes and reports keys missing from either side, size mismatches
+/// and ETag mismatches. Each side has its own client so the prefixes can live in
+/// different accounts or regions; pass the same client twice otherwise. ETag
+/// mismatches are checked with a HEAD on each side, and objects encrypted with
+/// SSE-KMS or SSE-C, whose ETags aren't MD5s, are reported as unverified.
+pub fn diff(left_client: &S3Client, left: &S3Location, right_client: &S3Client, right: &S3Location) -> Result<PrefixDiff, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let left_prefix = as_prefix(&left.key);
+        let right_prefix = as_prefix(&right.key);
+        let (left_objects, right_objects) = try_join(
+            s3_list(left_client, &left.bucket, &left_prefix, &ObjectFilter::new()),
+            s3_list(right_client, &right.bucket, &right_prefix, &ObjectFilter::new()),
+        ).await?;
+        let mut diff = PrefixDiff::compare(&left.to_string(), &left_prefix, &left_objects, &right.to_string(), &right_prefix, &right_objects);
+
+        let mismatched = diff.differences.iter()
+            .filter(|d| d.kind == DiffKind::ETagMismatch)
+            .map(|d| d.key.clone())
+            .collect::<Vec<String>>();
+        let (left_prefix, right_prefix) = (&left_prefix, &right_prefix);
+        let checks = stream::iter(mismatched.into_iter().map(|key| async move {
+            let (l, r) = join(
+                head(left_client, &S3Location::new(&left.bucket, &format!("{}{}", left_prefix, key))),
+                head(right_client, &S3Location::new(&right.bucket, &format!("{}{}", right_prefix, key))),
+            ).await;
+            (key, has_md5_e_tag(l) && has_md5_e_tag(r))
+        }))
+            .buffer_unordered(HEAD_CONCURRENCY)
+            .collect::<Vec<(String, bool)>>().await;
+        let unverifiable = checks.into_iter().filter(|(_, md5)| !md5).map(|(key, _)| key).collect::<Vec<String>>();
+        diff.mark_unverified(&unverifiable);
+        Ok(diff)
+    })
+}
+
+/// Objects that can't be read without a customer key fail the HEAD, so they
+/// count as unverifiable along with SSE-KMS objects.
+fn has_md5_e_tag(head: Result<ObjectMetadata, String>) -> bool {
+    match head {
+        Ok(metadata) => !metadata.server_side_encryption.unwrap_or_default().starts_with("aws:kms"),
+        Err(_e) => false,
+    }
+}
@@ -0,0 +1,232 @@
+use std::cmp::min;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use futures::stream::{self, StreamExt};
+use rusoto_s3::{GetObjectRequest, HeadObjectRequest, S3, S3Client};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::runtime::Runtime;
+use crate::errors::models::error_response::ErrorResponse;
+use crate::s3::models::download::{DownloadOptions, DownloadReport};
+use crate::s3::models::etag::{self, ETagHasher};
+use crate::s3::models::s3_location::S3Location;
+
+pub(crate) struct ObjectHead {
+    pub size: u64,
+    pub e_tag: Option<String>,
+    pub part_size: Option<u64>,
+    pub version_id: Option<String>,
+    pub verifiable: bool,
+}
+
+pub fn get_object_to_file(client: &S3Client, location: &S3Location, path: &Path, options: &DownloadOptions) -> Result<DownloadReport, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async { download_to_file(client, location, path, options).await })
+}
+
+pub fn get_object_to_bytes(client: &S3Client, location: &S3Location, options: &DownloadOptions) -> Result<Vec<u8>, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async { download_to_bytes(client, location, options).await })
+}
+
+pub async fn download_to_bytes(client: &S3Client, location: &S3Location, options: &DownloadOptions) -> Result<Vec<u8>, String> {
+    let mut buffer: Vec<u8> = vec![];
+    stream_object(client, location, &mut buffer, options).await.map(|_| buffer)
+}
+
+/// Streams an object into `writer`, fetching up to `options.concurrency`
+/// ranges in parallel but always writing them in order.
+pub async fn stream_object<W: AsyncWrite + Unpin>(client: &S3Client, location: &S3Location, writer: &mut W, options: &DownloadOptions) -> Result<DownloadReport, String> {
+    let head = head_for_download(client, location).await?;
+    let mut hasher = ETagHasher::new(head.part_size);
+    let bytes = transfer(client, location, &head, writer, 0, &mut hasher, options).await?;
+    let verified = verify(&head, hasher, options)?;
+    Ok(report(location, &head, bytes, 0, verified))
+}
+
+pub async fn download_to_file(client: &S3Client, location: &S3Location, path: &Path, options: &DownloadOptions) -> Result<DownloadReport, String> {
+    let head = head_for_download(client, location).await?;
+    let part_path = with_suffix(path, ".part");
+    let e_tag_path = with_suffix(path, ".part.etag");
+
+    let offset = if options.resume { resumable_offset(&part_path, &e_tag_path, &head).await } else { 0 };
+    let mut hasher = ETagHasher::new(head.part_size);
+    let mut file = if offset > 0 {
+        hash_file(&part_path, &mut hasher).await?;
+        OpenOptions::new().append(true).open(&part_path).await.map_err(io_error)?
+    } else {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await.map_err(io_error)?;
+        }
+        if let Some(e_tag) = &head.e_tag {
+            tokio::fs::write(&e_tag_path, e_tag.as_bytes()).await.map_err(io_error)?;
+        }
+        File::create(&part_path).await.map_err(io_error)?
+    };
+
+    let bytes = transfer(client, location, &head, &mut file, offset, &mut hasher, options).await?;
+    drop(file);
+    let verified = match verify(&head, hasher, options) {
+        Ok(verified) => verified,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&part_path).await;
+            let _ = tokio::fs::remove_file(&e_tag_path).await;
+            return Err(e);
+        }
+    };
+    tokio::fs::rename(&part_path, path).await.map_err(io_error)?;
+    let _ = tokio::fs::remove_file(&e_tag_path).await;
+    Ok(report(location, &head, bytes + offset, offset, verified))
+}
+
+pub(crate) async fn head_for_download(client: &S3Client, location: &S3Location) -> Result<ObjectHead, String> {
+    let head = client.head_object(HeadObjectRequest {
+        bucket: location.bucket.clone(),
+        key: location.key.clone(),
+        ..HeadObjectRequest::default()
+    }).await.map_err(|e| ErrorResponse::json(e.to_string().as_str()))?;
+
+    let e_tag = head.e_tag.as_ref().map(|t| etag::normalise(t));
+    let part_size = match &e_tag {
+        Some(t) if etag::is_multipart(t) => client.head_object(HeadObjectRequest {
+            bucket: location.bucket.clone(),
+            key: location.key.clone(),
+            part_number: Some(1),
+            version_id: head.version_id.clone(),
+            ..HeadObjectRequest::default()
+        }).await.map_err(|e| ErrorResponse::json(e.to_string().as_str()))?.content_length.map(|l| l as u64),
+        _ => None,
+    };
+    let encrypted_with_kms_or_customer_key = head.server_side_encryption.as_deref() == Some("aws:kms") ||
+        head.sse_customer_algorithm.is_some();
+
+    Ok(ObjectHead {
+        size: head.content_length.unwrap_or(0) as u64,
+        verifiable: e_tag.is_some() && !encrypted_with_kms_or_customer_key,
+        e_tag,
+        part_size,
+        version_id: head.version_id,
+    })
+}
+
+async fn transfer<W: AsyncWrite + Unpin>(client: &S3Client, location: &S3Location, head: &ObjectHead, writer: &mut W, offset: u64, hasher: &mut ETagHasher, options: &DownloadOptions) -> Result<u64, String> {
+    let mut parts = stream::iter(byte_ranges(offset, head.size, options.part_size).into_iter()
+        .map(|(start, end)| fetch_range(client, location, head, start, end)))
+        .buffered(options.concurrency.max(1));
+
+    let mut written = 0;
+    while let Some(part) = parts.next().await {
+        let data = part?;
+        hasher.update(&data);
+        writer.write_all(&data).await.map_err(io_error)?;
+        written += data.len() as u64;
+    }
+    writer.flush().await.map_err(io_error)?;
+    Ok(written)
+}
+
+async fn fetch_range(client: &S3Client, location: &S3Location, head: &ObjectHead, start: u64, end: u64) -> Result<Vec<u8>, String> {
+    let output = client.get_object(GetObjectRequest {
+        bucket: location.bucket.clone(),
+        key: location.key.clone(),
+        range: Some(format!("bytes={}-{}", start, end)),
+        if_match: head.e_tag.as_ref().map(|t| format!("\"{}\"", t)),
+        version_id: head.version_id.clone(),
+        ..GetObjectRequest::default()
+    }).await.map_err(|e| ErrorResponse::json(e.to_string().as_str()))?;
+
+    let mut data = Vec::with_capacity((end - start + 1) as usize);
+    if let Some(mut body) = output.body {
+        while let Some(chunk) = body.next().await {
+            data.extend_from_slice(&chunk.map_err(io_error)?);
+        }
+    }
+    Ok(data)
+}
+
+/// Feeds an existing partial download to the hasher a chunk at a time, as it
+/// can be most of a very large object.
+pub(crate) async fn hash_file(path: &Path, hasher: &mut ETagHasher) -> Result<(), String> {
+    let mut file = File::open(path).await.map_err(io_error)?;
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await.map_err(io_error)?;
+        if read == 0 { break; }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(())
+}
+
+async fn resumable_offset(part_path: &Path, e_tag_path: &Path, head: &ObjectHead) -> u64 {
+    let stored_e_tag = match tokio::fs::read_to_string(e_tag_path).await {
+        Ok(e_tag) => Some(e_tag),
+        Err(_e) => None,
+    };
+    if head.e_tag.is_none() || stored_e_tag != head.e_tag { return 0; }
+    match tokio::fs::metadata(part_path).await {
+        Ok(metadata) if metadata.len() <= head.size => metadata.len(),
+        _ => 0,
+    }
+}
+
+fn verify(head: &ObjectHead, hasher: ETagHasher, options: &DownloadOptions) -> Result<bool, String> {
+    if !options.verify || !head.verifiable { return Ok(false); }
+    let actual = hasher.finish();
+    match &head.e_tag {
+        Some(expected) if *expected == actual => Ok(true),
+        Some(expected) => Err(ErrorResponse::json(format!("ETag mismatch: expected {} but downloaded content hashes to {}", expected, actual).as_str())),
+        None => Ok(false),
+    }
+}
+
+fn report(location: &S3Location, head: &ObjectHead, bytes: u64, resumed_from: u64, verified: bool) -> DownloadReport {
+    DownloadReport {
+        bucket: location.bucket.clone(),
+        key: location.key.clone(),
+        bytes,
+        resumed_from,
+        e_tag: head.e_tag.clone(),
+        verified,
+    }
+}
+
+/// Inclusive byte ranges covering `[offset, size)` in chunks of `part_size`.
+pub(crate) fn byte_ranges(offset: u64, size: u64, part_size: u64) -> Vec<(u64, u64)> {
+    let part_size = part_size.max(1);
+    let mut ranges = vec![];
+    let mut start = offset;
+    while start < size {
+        let end = min(start + part_size, size) - 1;
+        ranges.push((start, end));
+        start = end + 1;
+    }
+    ranges
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name: OsString = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn io_error(e: std::io::Error) -> String {
+    ErrorResponse::json(e.to_string().as_str())
+}
+
+#[test]
+fn byte_ranges_cover_the_whole_object() {
+    assert_eq!(vec![(0, 3), (4, 7), (8, 9)], byte_ranges(0, 10, 4));
+    assert_eq!(vec![(0, 9)], byte_ranges(0, 10, 100));
+    assert!(byte_ranges(0, 0, 4).is_empty());
+}
+
+#[test]
+fn byte_ranges_start_from_resume_offset() {
+    assert_eq!(vec![(6, 9)], byte_ranges(6, 10, 4));
+    assert!(byte_ranges(10, 10, 4).is_empty());
+}
+
+#[test]
+fn partial_files_keep_the_original_name() {
+    assert_eq!(PathBuf::from("/tmp/data.csv.part"), with_suffix(Path::new("/tmp/data.csv"), ".part"));
+}
@@ -0,0 +1,107 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader};
+use flate2::read::MultiGzDecoder;
+use rusoto_s3::S3Client;
+use crate::errors::models::error_response::ErrorResponse;
+use tokio::runtime::Runtime;
+use crate::s3::download::{download_to_bytes, get_object_to_bytes};
+use crate::s3::models::download::DownloadOptions;
+use crate::s3::models::inventory::InventoryManifest;
+use crate::s3::models::object_filter::ObjectFilter;
+use crate::s3::models::s3_list_object::S3ListObject;
+use crate::s3::models::s3_location::S3Location;
+
+/// Lists a bucket from an S3 Inventory report instead of `ListObjectsV2`,
+/// returning the same entries `s3::ls_filtered` would.
+pub fn ls_inventory(client: &S3Client, manifest: &S3Location, filter: &ObjectFilter) -> Result<Vec<S3ListObject>, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async { list_inventory(client, manifest, filter).await })
+}
+
+/// `ls_inventory` for callers already running on a runtime. The whole listing is
+/// held in memory; use `InventoryReader` from synchronous code to stream it.
+pub async fn list_inventory(client: &S3Client, manifest_location: &S3Location, filter: &ObjectFilter) -> Result<Vec<S3ListObject>, String> {
+    let manifest = read_manifest(client, manifest_location).await?;
+    let mut objects = vec![];
+    for file in &manifest.files {
+        let location = manifest.data_location(&file.key);
+        let compressed = download_to_bytes(client, &location, &DownloadOptions::default()).await?;
+        for line in decode_lines(&compressed, &location)? {
+            if let Some(object) = manifest.parse_row(&line).map_err(|e| ErrorResponse::json(e.as_str()))? {
+                if filter.matches(&object) { objects.push(object); }
+            }
+        }
+    }
+    Ok(objects)
+}
+
+/// Reads an inventory report one data file at a time, so reports far larger than
+/// memory can be streamed.
+pub struct InventoryReader {
+    client: S3Client,
+    manifest: InventoryManifest,
+    next_file: usize,
+    pending: VecDeque<String>,
+    failed: bool,
+}
+
+impl InventoryReader {
+    pub fn open(client: &S3Client, manifest_location: &S3Location) -> Result<InventoryReader, String> {
+        let mut rt = Runtime::new().unwrap();
+        let manifest = rt.block_on(async { read_manifest(client, manifest_location).await })?;
+        Ok(InventoryReader { client: client.clone(), manifest, next_file: 0, pending: VecDeque::new(), failed: false })
+    }
+
+    pub fn manifest(&self) -> &InventoryManifest {
+        &self.manifest
+    }
+
+    fn read_next_file(&mut self) -> Result<(), String> {
+        let location = self.manifest.data_location(&self.manifest.files[self.next_file].key);
+        self.next_file += 1;
+        let compressed = get_object_to_bytes(&self.client, &location, &DownloadOptions::default())?;
+        self.pending.extend(decode_lines(&compressed, &location)?);
+        Ok(())
+    }
+}
+
+async fn read_manifest(client: &S3Client, manifest_location: &S3Location) -> Result<InventoryManifest, String> {
+    let json = download_to_bytes(client, manifest_location, &DownloadOptions::default()).await?;
+    let manifest = InventoryManifest::from_json(&json).map_err(|e| ErrorResponse::json(e.as_str()))?;
+    if !manifest.is_csv() {
+        return Err(ErrorResponse::json(format!("{} inventory reports are not supported, only CSV", manifest.file_format).as_str()));
+    }
+    Ok(manifest)
+}
+
+/// The non-empty lines of a gzipped data file.
+fn decode_lines(compressed: &[u8], location: &S3Location) -> Result<Vec<String>, String> {
+    let mut lines = vec![];
+    for line in BufReader::new(MultiGzDecoder::new(compressed)).lines() {
+        let line = line.map_err(|e| ErrorResponse::json(format!("reading {}: {}", location, e).as_str()))?;
+        if !line.is_empty() { lines.push(line); }
+    }
+    Ok(lines)
+}
+
+impl Iterator for InventoryReader {
+    type Item = Result<S3ListObject, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed {
+            if let Some(line) = self.pending.pop_front() {
+                match self.manifest.parse_row(&line) {
+                    Ok(Some(object)) => return Some(Ok(object)),
+                    Ok(None) => continue,
+                    Err(e) => return Some(Err(ErrorResponse::json(e.as_str()))),
+                }
+            }
+            if self.next_file >= self.manifest.files.len() { return None; }
+            if let Err(e) = self.read_next_file() {
+                self.failed = true;
+                return Some(Err(e));
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use rusoto_s3::{DeleteBucketLifecycleRequest, GetBucketLifecycleConfigurationRequest, PutBucketLifecycleConfigurationRequest, S3, S3Client};
+use tokio::runtime::Runtime;
+use crate::errors::models::error_response::ErrorResponse;
+use crate::s3::models::lifecycle::{LifecycleConfiguration, LifecyclePrediction};
+use crate::s3::models::object_filter::ObjectFilter;
+use crate::s3::models::s3_location::S3Location;
+use crate::s3::s3::s3_list;
+
+/// The bucket's lifecycle rules; a bucket without a configuration has none.
+pub fn get_lifecycle(client: &S3Client, bucket: &str) -> Result<LifecycleConfiguration, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        match client.get_bucket_lifecycle_configuration(GetBucketLifecycleConfigurationRequest {
+            bucket: bucket.to_string(),
+        }).await {
+            Ok(output) => Ok(LifecycleConfiguration::from(&output.rules.unwrap_or_default())),
+            Err(e) if e.to_string().contains("NoSuchLifecycleConfiguration") => Ok(LifecycleConfiguration::default()),
+            Err(e) => Err(ErrorResponse::json(e.to_string().as_str())),
+        }
+    })
+}
+
+/// Replaces the bucket's lifecycle rules after validating them. An empty
+/// configuration removes the rules altogether.
+pub fn put_lifecycle(client: &S3Client, bucket: &str, configuration: &LifecycleConfiguration) -> Result<(), String> {
+    configuration.validate().map_err(|problems| ErrorResponse::json(problems.join("; ").as_str()))?;
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        if configuration.rules.is_empty() {
+            client.delete_bucket_lifecycle(DeleteBucketLifecycleRequest {
+                bucket: bucket.to_string(),
+            }).await.map_err(|e| ErrorResponse::json(e.to_string().as_str()))
+        } else {
+            client.put_bucket_lifecycle_configuration(PutBucketLifecycleConfigurationRequest {
+                bucket: bucket.to_string(),
+                lifecycle_configuration: Some(configuration.to_rusoto()),
+            }).await.map_err(|e| ErrorResponse::json(e.to_string().as_str()))
+        }
+    })
+}
+
+/// Lists the location and predicts what the configuration will have done to
+/// each object by `as_of`, without changing anything.
+pub fn simulate_lifecycle(client: &S3Client, location: &S3Location, configuration: &LifecycleConfiguration, as_of: DateTime<Utc>) -> Result<Vec<LifecyclePrediction>, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let objects = s3_list(client, &location.bucket, &location.key, &ObjectFilter::new()).await?;
+        Ok(configuration.simulate(&objects, as_of))
+    })
+}
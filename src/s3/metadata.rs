@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+use rusoto_s3::{DeleteObjectTaggingRequest, GetObjectTaggingRequest, HeadObjectRequest, PutObjectTaggingRequest};
+use rusoto_s3::{S3, S3Client, Tag, Tagging};
+use tokio::runtime::Runtime;
+use crate::errors::models::error_response::ErrorResponse;
+use crate::s3::models::etag;
+use crate::s3::models::object_metadata::{parse_http_date, ObjectMetadata, RestoreStatus};
+use crate::s3::models::s3_location::S3Location;
+
+pub fn stat(client: &S3Client, location: &S3Location) -> Result<ObjectMetadata, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async { head(client, location).await })
+}
+
+pub fn get_tags(client: &S3Client, location: &S3Location) -> Result<BTreeMap<String, String>, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        match client.get_object_tagging(GetObjectTaggingRequest {
+            bucket: location.bucket.clone(),
+            key: location.key.clone(),
+            ..GetObjectTaggingRequest::default()
+        }).await {
+            Ok(output) => Ok(output.tag_set.into_iter().map(|tag| (tag.key, tag.value)).collect()),
+            Err(_e) => Err(ErrorResponse::json(_e.to_string().as_str())),
+        }
+    })
+}
+
+/// Replaces the whole tag set on the object.
+pub fn put_tags(client: &S3Client, location: &S3Location, tags: &BTreeMap<String, String>) -> Result<(), String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        match client.put_object_tagging(PutObjectTaggingRequest {
+            bucket: location.bucket.clone(),
+            key: location.key.clone(),
+            tagging: Tagging { tag_set: tags.iter().map(|(k, v)| Tag { key: k.clone(), value: v.clone() }).collect() },
+            ..PutObjectTaggingRequest::default()
+        }).await {
+            Ok(_) => Ok(()),
+            Err(_e) => Err(ErrorResponse::json(_e.to_string().as_str())),
+        }
+    })
+}
+
+pub fn delete_tags(client: &S3Client, location: &S3Location) -> Result<(), String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        match client.delete_object_tagging(DeleteObjectTaggingRequest {
+            bucket: location.bucket.clone(),
+            key: location.key.clone(),
+            ..DeleteObjectTaggingRequest::default()
+        }).await {
+            Ok(_) => Ok(()),
+            Err(_e) => Err(ErrorResponse::json(_e.to_string().as_str())),
+        }
+    })
+}
+
+pub(crate) async fn head(client: &S3Client, location: &S3Location) -> Result<ObjectMetadata, String> {
+    match client.head_object(HeadObjectRequest {
+        bucket: location.bucket.clone(),
+        key: location.key.clone(),
+        ..HeadObjectRequest::default()
+    }).await {
+        Ok(output) => Ok(ObjectMetadata {
+            bucket: location.bucket.clone(),
+            key: location.key.clone(),
+            content_length: output.content_length.unwrap_or(0),
+            content_type: output.content_type,
+            e_tag: output.e_tag.as_ref().map(|t| etag::normalise(t)),
+            last_modified: output.last_modified.as_ref().and_then(|d| parse_http_date(d)),
+            storage_class: output.storage_class.unwrap_or_else(|| "STANDARD".to_string()),
+            server_side_encryption: output.server_side_encryption,
+            kms_key_id: output.ssekms_key_id,
+            version_id: output.version_id,
+            metadata: output.metadata.unwrap_or_default().into_iter().collect(),
+            restore: output.restore.as_ref().and_then(|r| RestoreStatus::from(r)),
+        }),
+        Err(_e) => Err(ErrorResponse::json(_e.to_string().as_str())),
+    }
+}
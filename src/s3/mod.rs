@@ -1,2 +1,19 @@
 pub mod s3;
-pub mod models;
\ No newline at end of file
+pub mod download;
+pub mod upload;
+pub mod operations;
+pub mod sync;
+pub mod metadata;
+pub mod versions;
+pub mod restore;
+pub mod presign;
+pub mod partitions;
+pub mod output;
+pub mod diff;
+pub mod select;
+pub mod inventory;
+pub mod buckets;
+pub mod lifecycle;
+pub mod multipart;
+pub mod object_store;
+pub mod models;
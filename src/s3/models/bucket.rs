@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+use regex::Regex;
+use serde_derive::Serialize;
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct S3Bucket {
+    pub name: String,
+    pub creation_date: Option<String>,
+}
+
+impl S3Bucket {
+    pub fn from(b: &rusoto_s3::Bucket) -> S3Bucket {
+        S3Bucket {
+            name: b.name.clone().unwrap_or_default(),
+            creation_date: b.creation_date.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct BucketEncryption {
+    pub algorithm: String,
+    pub kms_key_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PublicAccessBlock {
+    pub block_public_acls: bool,
+    pub ignore_public_acls: bool,
+    pub block_public_policy: bool,
+    pub restrict_public_buckets: bool,
+}
+
+impl PublicAccessBlock {
+    pub fn from(c: &rusoto_s3::PublicAccessBlockConfiguration) -> PublicAccessBlock {
+        PublicAccessBlock {
+            block_public_acls: c.block_public_acls.unwrap_or(false),
+            ignore_public_acls: c.ignore_public_acls.unwrap_or(false),
+            block_public_policy: c.block_public_policy.unwrap_or(false),
+            restrict_public_buckets: c.restrict_public_buckets.unwrap_or(false),
+        }
+    }
+
+    pub fn is_fully_blocked(&self) -> bool {
+        self.block_public_acls && self.ignore_public_acls && self.block_public_policy && self.restrict_public_buckets
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct BucketLogging {
+    pub target_bucket: String,
+    pub target_prefix: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct BucketReplication {
+    pub role: String,
+    pub rules: usize,
+}
+
+/// The bucket settings a compliance audit looks at. Settings the bucket has
+/// never had configured are `None` or empty rather than errors.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct BucketInfo {
+    pub name: String,
+    pub region: String,
+    pub versioning: Option<String>,
+    pub mfa_delete: Option<String>,
+    pub encryption: Vec<BucketEncryption>,
+    pub lifecycle_rule_ids: Vec<String>,
+    pub public_access_block: Option<PublicAccessBlock>,
+    pub logging: Option<BucketLogging>,
+    pub replication: Option<BucketReplication>,
+    pub tags: BTreeMap<String, String>,
+}
+
+impl BucketInfo {
+    pub fn is_versioned(&self) -> bool {
+        self.versioning.as_deref() == Some("Enabled")
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        !self.encryption.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// GetBucketLocation reports us-east-1 as no constraint and eu-west-1 by its
+/// legacy name `EU`.
+pub fn region_from_location_constraint(constraint: Option<&str>) -> String {
+    match constraint {
+        None | Some("") => "us-east-1".to_string(),
+        Some("EU") => "eu-west-1".to_string(),
+        Some(region) => region.to_string(),
+    }
+}
+
+/// The region named in the body of a redirect or wrong-region error.
+pub fn region_from_error_body(body: &str) -> Option<String> {
+    let re = Regex::new(r"<Region>([a-z0-9-]+)</Region>").unwrap();
+    re.captures(body).map(|cap| cap[1].to_string())
+}
+
+#[test]
+fn location_constraints_map_to_regions() {
+    assert_eq!("us-east-1", region_from_location_constraint(None));
+    assert_eq!("us-east-1", region_from_location_constraint(Some("")));
+    assert_eq!("eu-west-1", region_from_location_constraint(Some("EU")));
+    assert_eq!("ap-southeast-2", region_from_location_constraint(Some("ap-southeast-2")));
+}
+
+#[test]
+fn region_is_read_from_redirect_body() {
+    let body = "<Error><Code>AuthorizationHeaderMalformed</Code><Region>eu-west-2</Region></Error>";
+    assert_eq!(Some("eu-west-2".to_string()), region_from_error_body(body));
+    assert_eq!(None, region_from_error_body("<Error><Code>AccessDenied</Code></Error>"));
+}
@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use serde_derive::Serialize;
+use crate::s3::models::etag;
+use crate::s3::models::s3_list_object::S3ListObject;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    OnlyLeft,
+    OnlyRight,
+    SizeMismatch,
+    ETagMismatch,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct DiffSide {
+    pub size: i64,
+    pub e_tag: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct DiffEntry {
+    pub key: String,
+    pub kind: DiffKind,
+    pub left: Option<DiffSide>,
+    pub right: Option<DiffSide>,
+}
+
+/// Differences between two prefixes, keyed by path relative to each prefix.
+/// Keys whose sizes match but whose ETags come from different upload layouts
+/// can't be verified from a listing and are reported as `unverified`.
+#[derive(Debug, Serialize, Clone, PartialEq, Default)]
+pub struct PrefixDiff {
+    pub left: String,
+    pub right: String,
+    pub matching: usize,
+    pub unverified: Vec<String>,
+    pub differences: Vec<DiffEntry>,
+}
+
+impl PrefixDiff {
+    pub fn compare(left: &str, left_prefix: &str, left_objects: &[S3ListObject], right: &str, right_prefix: &str, right_objects: &[S3ListObject]) -> PrefixDiff {
+        let left_sides = by_relative_key(left_prefix, left_objects);
+        let mut right_sides = by_relative_key(right_prefix, right_objects);
+        let mut diff = PrefixDiff { left: left.to_string(), right: right.to_string(), ..PrefixDiff::default() };
+
+        for (key, l) in left_sides {
+            let entry = |kind, right| DiffEntry { key: key.clone(), kind, left: Some(l.clone()), right };
+            match right_sides.remove(&key) {
+                None => diff.differences.push(entry(DiffKind::OnlyLeft, None)),
+                Some(r) if r.size != l.size => diff.differences.push(entry(DiffKind::SizeMismatch, Some(r))),
+                Some(r) => match (&l.e_tag, &r.e_tag) {
+                    (Some(a), Some(b)) if a == b => diff.matching += 1,
+                    (Some(a), Some(b)) if etag::is_comparable(a, b) => diff.differences.push(entry(DiffKind::ETagMismatch, Some(r.clone()))),
+                    _ => diff.unverified.push(key.clone()),
+                },
+            }
+        }
+        diff.differences.extend(right_sides.into_iter().map(|(key, r)| DiffEntry { key, kind: DiffKind::OnlyRight, left: None, right: Some(r) }));
+        diff.differences.sort_by(|a, b| a.key.cmp(&b.key));
+        diff
+    }
+
+    /// Moves ETag mismatches for `keys` to `unverified`, for objects whose ETags
+    /// turn out not to be MD5s.
+    pub fn mark_unverified(&mut self, keys: &[String]) {
+        let (unverified, differences): (Vec<DiffEntry>, Vec<DiffEntry>) = self.differences.drain(..)
+            .partition(|d| d.kind == DiffKind::ETagMismatch && keys.contains(&d.key));
+        self.differences = differences;
+        self.unverified.extend(unverified.into_iter().map(|d| d.key));
+        self.unverified.sort();
+    }
+
+    pub fn is_identical(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+fn by_relative_key(prefix: &str, objects: &[S3ListObject]) -> BTreeMap<String, DiffSide> {
+    objects.iter()
+        .map(|o| (o.key.strip_prefix(prefix).unwrap_or(o.key.as_str()).to_string(), DiffSide { size: o.size, e_tag: o.e_tag.clone() }))
+        .collect()
+}
+
+#[cfg(test)]
+fn object(key: &str, size: i64, e_tag: &str) -> S3ListObject {
+    S3ListObject { last_modified: String::new(), size, key: key.to_string(), storage_class: None, e_tag: Some(e_tag.to_string()) }
+}
+
+#[cfg(test)]
+const X: &str = "5d41402abc4b2a76b9719d911017c592";
+#[cfg(test)]
+const Y: &str = "0123456789abcdef0123456789abcdef";
+
+#[test]
+fn diff_reports_each_kind_of_difference() {
+    let left = vec![
+        object("a/same", 1, X),
+        object("a/left-only", 1, X),
+        object("a/resized", 1, X),
+        object("a/changed", 1, X),
+        object("a/reuploaded", 1, X),
+    ];
+    let right = vec![
+        object("b/same", 1, X),
+        object("b/right-only", 1, X),
+        object("b/resized", 2, X),
+        object("b/changed", 1, Y),
+        object("b/reuploaded", 1, &format!("{}-2", Y)),
+    ];
+    let diff = PrefixDiff::compare("s3://l/a/", "a/", &left, "s3://r/b/", "b/", &right);
+
+    assert_eq!(1, diff.matching);
+    assert_eq!(vec!["reuploaded".to_string()], diff.unverified);
+    assert_eq!(
+        vec![("changed", DiffKind::ETagMismatch), ("left-only", DiffKind::OnlyLeft), ("resized", DiffKind::SizeMismatch), ("right-only", DiffKind::OnlyRight)],
+        diff.differences.iter().map(|d| (d.key.as_str(), d.kind)).collect::<Vec<(&str, DiffKind)>>()
+    );
+    assert!(!diff.is_identical());
+}
+
+#[test]
+fn multipart_etags_with_equal_part_counts_are_unverified() {
+    let left = vec![object("a/big", 10, &format!("{}-3", X))];
+    let right = vec![object("b/big", 10, &format!("{}-3", Y))];
+    let diff = PrefixDiff::compare("s3://l/a/", "a/", &left, "s3://r/b/", "b/", &right);
+    assert_eq!(vec!["big".to_string()], diff.unverified);
+    assert!(diff.is_identical());
+}
+
+#[test]
+fn encrypted_mismatches_can_be_marked_unverified() {
+    let left = vec![object("a/kms", 1, X), object("a/changed", 1, X)];
+    let right = vec![object("b/kms", 1, Y), object("b/changed", 1, Y)];
+    let mut diff = PrefixDiff::compare("s3://l/a/", "a/", &left, "s3://r/b/", "b/", &right);
+    diff.mark_unverified(&["kms".to_string()]);
+    assert_eq!(vec!["kms".to_string()], diff.unverified);
+    assert_eq!(vec!["changed"], diff.differences.iter().map(|d| d.key.as_str()).collect::<Vec<&str>>());
+}
@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+use serde_derive::Serialize;
+use crate::s3::models::s3_list_object::S3ListObject;
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub struct UsageTotals {
+    pub objects: i64,
+    pub bytes: i64,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct DiskUsage {
+    pub prefix: String,
+    pub depth: usize,
+    pub objects: i64,
+    pub bytes: i64,
+    pub storage_classes: BTreeMap<String, UsageTotals>,
+}
+
+impl DiskUsage {
+    fn empty(prefix: &str, depth: usize) -> DiskUsage {
+        DiskUsage {
+            prefix: prefix.to_string(),
+            depth,
+            objects: 0,
+            bytes: 0,
+            storage_classes: BTreeMap::new(),
+        }
+    }
+
+    fn add(&mut self, object: &S3ListObject) {
+        let storage_class = object.storage_class.clone().unwrap_or_else(|| "STANDARD".to_string());
+        let totals = self.storage_classes.entry(storage_class).or_default();
+        totals.objects += 1;
+        totals.bytes += object.size;
+        self.objects += 1;
+        self.bytes += object.size;
+    }
+
+    /// Totals the listing under `prefix`, with one entry for the prefix itself
+    /// and one for every sub-prefix down to `depth` levels below it. Like `du`,
+    /// each entry includes everything beneath it. Entries are ordered by prefix,
+    /// so the root entry always comes first.
+    pub fn summarise(prefix: &str, objects: &[S3ListObject], depth: usize) -> Vec<DiskUsage> {
+        let mut usage: BTreeMap<String, DiskUsage> = BTreeMap::new();
+        usage.insert(prefix.to_string(), DiskUsage::empty(prefix, 0));
+
+        objects.iter().for_each(|object| {
+            usage.get_mut(prefix).unwrap().add(object);
+            sub_prefixes(prefix, &object.key, depth).iter().enumerate().for_each(|(level, sub_prefix)| {
+                usage.entry(sub_prefix.clone())
+                    .or_insert_with(|| DiskUsage::empty(sub_prefix, level + 1))
+                    .add(object);
+            });
+        });
+
+        usage.into_values().collect()
+    }
+}
+
+fn sub_prefixes(prefix: &str, key: &str, depth: usize) -> Vec<String> {
+    if !key.starts_with(prefix) { return vec![]; }
+    let mut start = prefix.len();
+    if !prefix.is_empty() && !prefix.ends_with('/') && key[start..].starts_with('/') { start += 1; }
+
+    key[start..].match_indices('/')
+        .take(depth)
+        .map(|(i, _)| key[..start + i + 1].to_string())
+        .collect()
+}
+
+#[cfg(test)]
+fn test_object(key: &str, size: i64, storage_class: &str) -> S3ListObject {
+    S3ListObject {
+        last_modified: "2020-01-01T00:00:00.000Z".to_string(),
+        size,
+        key: key.to_string(),
+        storage_class: Some(storage_class.to_string()),
+        e_tag: None,
+    }
+}
+
+#[test]
+fn sub_prefixes_are_limited_by_depth() {
+    assert_eq!(vec!["t/a/".to_string()], sub_prefixes("t/", "t/a/b/c.csv", 1));
+    assert_eq!(vec!["t/a/".to_string(), "t/a/b/".to_string()], sub_prefixes("t/", "t/a/b/c.csv", 5));
+    assert_eq!(vec!["t/a/".to_string()], sub_prefixes("t", "t/a/b.csv", 1));
+    assert!(sub_prefixes("t/", "t/c.csv", 3).is_empty());
+}
+
+#[test]
+fn summarise_totals_each_level() {
+    let objects = vec![
+        test_object("t/dt=1/a.parquet", 10, "STANDARD"),
+        test_object("t/dt=1/b.parquet", 5, "GLACIER"),
+        test_object("t/dt=2/a.parquet", 7, "STANDARD"),
+        test_object("t/_SUCCESS", 0, "STANDARD"),
+    ];
+    let actual = DiskUsage::summarise("t/", &objects, 1);
+
+    assert_eq!(3, actual.len());
+    assert_eq!("t/", actual[0].prefix);
+    assert_eq!(4, actual[0].objects);
+    assert_eq!(22, actual[0].bytes);
+    assert_eq!(UsageTotals { objects: 1, bytes: 5 }, actual[0].storage_classes["GLACIER"]);
+    assert_eq!(UsageTotals { objects: 3, bytes: 17 }, actual[0].storage_classes["STANDARD"]);
+    assert_eq!("t/dt=1/", actual[1].prefix);
+    assert_eq!(1, actual[1].depth);
+    assert_eq!(15, actual[1].bytes);
+    assert_eq!("t/dt=2/", actual[2].prefix);
+    assert_eq!(1, actual[2].objects);
+}
+
+#[test]
+fn summarise_at_depth_zero_only_reports_root() {
+    let objects = vec![test_object("t/a/b.csv", 3, "STANDARD")];
+    let actual = DiskUsage::summarise("t/", &objects, 0);
+    assert_eq!(1, actual.len());
+    assert_eq!(3, actual[0].bytes);
+}
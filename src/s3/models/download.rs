@@ -0,0 +1,30 @@
+use serde_derive::Serialize;
+
+#[derive(Clone, Debug)]
+pub struct DownloadOptions {
+    pub part_size: u64,
+    pub concurrency: usize,
+    pub verify: bool,
+    pub resume: bool,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> DownloadOptions {
+        DownloadOptions {
+            part_size: 8 * 1024 * 1024,
+            concurrency: 4,
+            verify: true,
+            resume: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DownloadReport {
+    pub bucket: String,
+    pub key: String,
+    pub bytes: u64,
+    pub resumed_from: u64,
+    pub e_tag: Option<String>,
+    pub verified: bool,
+}
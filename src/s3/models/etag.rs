@@ -0,0 +1,148 @@
+use std::cmp::min;
+
+/// Strips the surrounding quotes S3 returns on ETag headers.
+pub fn normalise(e_tag: &str) -> String {
+    e_tag.trim_matches('"').to_string()
+}
+
+/// Number of parts for a multipart ETag (`<md5>-<parts>`), `None` for a single-part ETag.
+pub fn part_count(e_tag: &str) -> Option<u64> {
+    match normalise(e_tag).rsplit_once('-') {
+        Some((_, parts)) => parts.parse::<u64>().ok(),
+        None => None,
+    }
+}
+
+pub fn is_multipart(e_tag: &str) -> bool {
+    part_count(e_tag).is_some()
+}
+
+/// Whether an ETag has the shape of a plain MD5: 32 hex digits.
+pub fn is_md5(e_tag: &str) -> bool {
+    let e_tag = normalise(e_tag);
+    e_tag.len() == 32 && e_tag.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Whether differing ETags mean differing content: only when both are plain MD5s.
+/// Multipart ETags depend on the part size, which a listing doesn't reveal, so
+/// identical content uploaded with different part sizes carries different ETags
+/// even when the part counts match. SSE-KMS and SSE-C objects can have MD5-shaped
+/// ETags that aren't MD5s; those can only be told apart with a HEAD request.
+pub fn is_comparable(left: &str, right: &str) -> bool {
+    is_md5(left) && is_md5(right)
+}
+
+/// Incrementally computes the ETag S3 would report for the bytes fed to it.
+///
+/// With no part size this is the plain MD5 of the content. With a part size it
+/// reproduces the multipart form: the MD5 of the concatenated part MD5s,
+/// suffixed with the number of parts.
+pub struct ETagHasher {
+    part_size: Option<u64>,
+    current: md5::Context,
+    current_len: u64,
+    part_digests: Vec<md5::Digest>,
+}
+
+impl ETagHasher {
+    pub fn new(part_size: Option<u64>) -> ETagHasher {
+        ETagHasher {
+            part_size: part_size.filter(|size| *size > 0),
+            current: md5::Context::new(),
+            current_len: 0,
+            part_digests: vec![],
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self.part_size {
+            Some(part_size) => {
+                let mut remaining = data;
+                while !remaining.is_empty() {
+                    let take = min((part_size - self.current_len) as usize, remaining.len());
+                    self.current.consume(&remaining[..take]);
+                    self.current_len += take as u64;
+                    remaining = &remaining[take..];
+                    if self.current_len == part_size {
+                        let finished = std::mem::replace(&mut self.current, md5::Context::new());
+                        self.part_digests.push(finished.compute());
+                        self.current_len = 0;
+                    }
+                }
+            },
+            None => self.current.consume(data),
+        }
+    }
+
+    pub fn finish(mut self) -> String {
+        match self.part_size {
+            Some(_) => {
+                if self.current_len > 0 || self.part_digests.is_empty() {
+                    self.part_digests.push(self.current.compute());
+                }
+                let mut combined = md5::Context::new();
+                self.part_digests.iter().for_each(|digest| combined.consume(digest.0));
+                format!("{:x}-{}", combined.compute(), self.part_digests.len())
+            },
+            None => format!("{:x}", self.current.compute()),
+        }
+    }
+}
+
+pub fn compute(data: &[u8], part_size: Option<u64>) -> String {
+    let mut hasher = ETagHasher::new(part_size);
+    hasher.update(data);
+    hasher.finish()
+}
+
+#[test]
+fn quotes_are_stripped_from_etags() {
+    assert_eq!("abc", normalise("\"abc\""));
+    assert_eq!("abc-2", normalise("abc-2"));
+}
+
+#[test]
+fn multipart_etags_are_recognised() {
+    assert_eq!(Some(12), part_count("\"0123456789abcdef0123456789abcdef-12\""));
+    assert_eq!(None, part_count("0123456789abcdef0123456789abcdef"));
+    assert!(!is_multipart("\"0123456789abcdef0123456789abcdef\""));
+}
+
+#[test]
+fn etags_are_comparable_only_when_both_are_md5s() {
+    let md5 = "5d41402abc4b2a76b9719d911017c592";
+    assert!(is_comparable(md5, "\"0123456789abcdef0123456789abcdef\""));
+    assert!(!is_comparable(md5, "0123456789abcdef0123456789abcdef-3"));
+    assert!(!is_comparable("0123456789abcdef0123456789abcdef-3", "\"5d41402abc4b2a76b9719d911017c592-3\""));
+}
+
+#[test]
+fn non_md5_etags_are_not_comparable() {
+    let md5 = "5d41402abc4b2a76b9719d911017c592";
+    assert!(!is_comparable(md5, "abc"));
+    assert!(!is_comparable("d41d8cd98f00b204e9800998ecf8427e5d41402a", md5));
+    assert!(!is_md5("5d41402abc4b2a76b9719d911017c59z"));
+}
+
+#[test]
+fn single_part_etag_is_content_md5() {
+    assert_eq!("5d41402abc4b2a76b9719d911017c592", compute(b"hello", None));
+}
+
+#[test]
+fn multipart_etag_is_md5_of_part_md5s() {
+    let part_one = md5::compute(b"hel");
+    let part_two = md5::compute(b"lo");
+    let mut combined = md5::Context::new();
+    combined.consume(part_one.0);
+    combined.consume(part_two.0);
+    let expected = format!("{:x}-2", combined.compute());
+
+    assert_eq!(expected, compute(b"hello", Some(3)));
+
+    let mut hasher = ETagHasher::new(Some(3));
+    hasher.update(b"h");
+    hasher.update(b"ell");
+    hasher.update(b"o");
+    assert_eq!(expected, hasher.finish());
+}
@@ -0,0 +1,150 @@
+use serde_derive::Deserialize;
+use crate::s3::models::etag;
+use crate::s3::models::s3_list_object::S3ListObject;
+use crate::s3::models::s3_location::{url_decode, S3Location};
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct InventoryFile {
+    pub key: String,
+    pub size: i64,
+    #[serde(rename = "MD5checksum")]
+    pub md5_checksum: String,
+}
+
+/// The `manifest.json` S3 Inventory writes alongside each report.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryManifest {
+    pub source_bucket: String,
+    pub destination_bucket: String,
+    pub file_format: String,
+    pub file_schema: String,
+    pub files: Vec<InventoryFile>,
+}
+
+impl InventoryManifest {
+    pub fn from_json(json: &[u8]) -> Result<InventoryManifest, String> {
+        serde_json::from_slice(json).map_err(|e| format!("invalid inventory manifest: {}", e))
+    }
+
+    /// The bucket holding the data files; the manifest gives it as an ARN.
+    pub fn data_bucket(&self) -> &str {
+        match self.destination_bucket.split_once(":s3:::") {
+            Some((prefix, bucket)) if prefix.starts_with("arn:") => bucket,
+            _ => &self.destination_bucket,
+        }
+    }
+
+    /// A data file's location, in the partition of the destination bucket's ARN.
+    pub fn data_location(&self, key: &str) -> S3Location {
+        match S3Location::from(&self.destination_bucket) {
+            Ok(location) if location.scheme == "arn" => S3Location { key: key.to_string(), ..location },
+            _ => S3Location::new(self.data_bucket(), key),
+        }
+    }
+
+    pub fn columns(&self) -> Vec<&str> {
+        self.file_schema.split(',').map(|c| c.trim()).collect()
+    }
+
+    pub fn is_csv(&self) -> bool {
+        self.file_format.eq_ignore_ascii_case("CSV")
+    }
+
+    /// One CSV row as a listing entry. Rows for noncurrent versions and delete
+    /// markers are skipped so versioned inventories list like `s3::ls`.
+    pub fn parse_row(&self, line: &str) -> Result<Option<S3ListObject>, String> {
+        let values = split_csv_row(line);
+        let columns = self.columns();
+        if values.len() != columns.len() {
+            return Err(format!("inventory row has {} fields, schema has {}: {}", values.len(), columns.len(), line));
+        }
+        let field = |name: &str| columns.iter().position(|c| *c == name).map(|i| values[i].as_str()).filter(|v| !v.is_empty());
+
+        if field("IsLatest") == Some("false") || field("IsDeleteMarker") == Some("true") {
+            return Ok(None);
+        }
+        let key = match field("Key") {
+            Some(key) => url_decode(&key.replace('+', " ")),
+            None => return Err(format!("inventory row has no key: {}", line)),
+        };
+        Ok(Some(S3ListObject {
+            last_modified: field("LastModifiedDate").unwrap_or_default().to_string(),
+            size: field("Size").and_then(|s| s.parse::<i64>().ok()).unwrap_or(0),
+            key,
+            storage_class: field("StorageClass").map(|s| s.to_string()),
+            e_tag: field("ETag").map(etag::normalise),
+        }))
+    }
+}
+
+/// Splits a row of the quoted CSV S3 Inventory writes.
+pub fn split_csv_row(line: &str) -> Vec<String> {
+    let mut values = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = line.trim_end_matches(['\r', '\n'].as_ref()).chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => { current.push('"'); chars.next(); },
+            '"' => quoted = !quoted,
+            ',' if !quoted => values.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    values.push(current);
+    values
+}
+
+#[cfg(test)]
+fn manifest(schema: &str) -> InventoryManifest {
+    manifest_in("arn:aws:s3:::dest", schema)
+}
+
+#[cfg(test)]
+fn manifest_in(destination_bucket: &str, schema: &str) -> InventoryManifest {
+    InventoryManifest::from_json(format!(
+        "{{\"sourceBucket\":\"src\",\"destinationBucket\":\"{}\",\"version\":\"2016-11-30\",\"fileFormat\":\"CSV\",\"fileSchema\":\"{}\",\"files\":[{{\"key\":\"inv/data/a.csv.gz\",\"size\":10,\"MD5checksum\":\"abc\"}}]}}",
+        destination_bucket, schema
+    ).as_bytes()).unwrap()
+}
+
+#[test]
+fn manifest_is_parsed() {
+    let manifest = manifest("Bucket, Key, Size");
+    assert_eq!("dest", manifest.data_bucket());
+    assert_eq!(vec!["Bucket", "Key", "Size"], manifest.columns());
+    assert_eq!("inv/data/a.csv.gz", manifest.files[0].key);
+    assert!(manifest.is_csv());
+}
+
+#[test]
+fn data_files_stay_in_the_destination_partition() {
+    let manifest = manifest_in("arn:aws-cn:s3:::dest", "Bucket, Key, Size");
+    assert_eq!("dest", manifest.data_bucket());
+    assert_eq!("arn:aws-cn:s3:::dest/inv/data/a.csv.gz", manifest.data_location("inv/data/a.csv.gz").to_string());
+    assert_eq!("dest", manifest_in("dest", "Key").data_location("a").bucket);
+}
+
+#[test]
+fn rows_become_list_objects() {
+    let manifest = manifest("Bucket, Key, Size, LastModifiedDate, ETag, StorageClass");
+    let object = manifest.parse_row("\"src\",\"dir/a+file%2Bname.txt\",\"42\",\"2020-01-01T00:00:00.000Z\",\"abc-2\",\"STANDARD\"").unwrap().unwrap();
+    assert_eq!("dir/a file+name.txt", object.key);
+    assert_eq!(42, object.size);
+    assert_eq!(Some("abc-2".to_string()), object.e_tag);
+    assert!(object.last_modified_date_time().is_some());
+}
+
+#[test]
+fn noncurrent_versions_are_skipped() {
+    let manifest = manifest("Bucket, Key, VersionId, IsLatest, IsDeleteMarker, Size");
+    assert!(manifest.parse_row("\"src\",\"a\",\"v1\",\"false\",\"false\",\"1\"").unwrap().is_none());
+    assert!(manifest.parse_row("\"src\",\"a\",\"v2\",\"true\",\"true\",\"\"").unwrap().is_none());
+    assert!(manifest.parse_row("\"src\",\"a\",\"v3\",\"true\",\"false\",\"1\"").unwrap().is_some());
+}
+
+#[test]
+fn quoted_fields_may_contain_commas_and_quotes() {
+    assert_eq!(vec!["a,b".to_string(), "say \"hi\"".to_string(), String::new()], split_csv_row("\"a,b\",\"say \"\"hi\"\"\",\"\"\n"));
+}
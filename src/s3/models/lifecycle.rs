@@ -0,0 +1,393 @@
+use std::collections::{BTreeMap, HashSet};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde_derive::Serialize;
+use crate::s3::models::s3_list_object::S3ListObject;
+
+pub const MAX_LIFECYCLE_RULES: usize = 1000;
+pub const MAX_RULE_ID_LENGTH: usize = 255;
+pub const MIN_INFREQUENT_ACCESS_DAYS: i64 = 30;
+
+/// Where a storage class sits in the order S3 allows transitions: objects only
+/// ever move down this list.
+pub fn storage_class_rank(storage_class: &str) -> u8 {
+    match storage_class {
+        "STANDARD_IA" => 1,
+        "INTELLIGENT_TIERING" => 2,
+        "ONEZONE_IA" => 3,
+        "GLACIER_IR" => 4,
+        "GLACIER" => 5,
+        "DEEP_ARCHIVE" => 6,
+        _ => 0,
+    }
+}
+
+/// When an action applies: a number of days after creation, or a fixed date.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleTiming {
+    Days(i64),
+    Date(DateTime<Utc>),
+}
+
+impl LifecycleTiming {
+    fn from(days: Option<i64>, date: Option<&String>) -> Option<LifecycleTiming> {
+        match (days, date.and_then(|d| DateTime::parse_from_rfc3339(d).ok())) {
+            (Some(days), _) => Some(LifecycleTiming::Days(days)),
+            (None, Some(date)) => Some(LifecycleTiming::Date(date.with_timezone(&Utc))),
+            (None, None) => None,
+        }
+    }
+
+    fn days(&self) -> Option<i64> {
+        match *self {
+            LifecycleTiming::Days(days) => Some(days),
+            LifecycleTiming::Date(_) => None,
+        }
+    }
+
+    fn date(&self) -> Option<String> {
+        match *self {
+            LifecycleTiming::Days(_) => None,
+            LifecycleTiming::Date(date) => Some(date.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()),
+        }
+    }
+
+    /// When the action falls due for an object created at `created`. S3 adds the
+    /// days to the creation time and rounds up to the next midnight UTC.
+    pub fn due(&self, created: DateTime<Utc>) -> DateTime<Utc> {
+        match *self {
+            LifecycleTiming::Date(date) => date,
+            LifecycleTiming::Days(days) => {
+                let at = created + Duration::days(days);
+                let midnight = at.naive_utc().date().and_hms_opt(0, 0, 0).unwrap();
+                if at.naive_utc() == midnight { at } else { Utc.from_utc_datetime(&(midnight + Duration::days(1))) }
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct LifecycleTransition {
+    pub timing: LifecycleTiming,
+    pub storage_class: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct NoncurrentTransition {
+    pub noncurrent_days: i64,
+    pub storage_class: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Default)]
+pub struct LifecycleFilter {
+    pub prefix: String,
+    pub tags: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct LifecycleRule {
+    pub id: String,
+    pub enabled: bool,
+    pub filter: LifecycleFilter,
+    pub transitions: Vec<LifecycleTransition>,
+    pub expiration: Option<LifecycleTiming>,
+    pub expired_object_delete_marker: bool,
+    pub noncurrent_transitions: Vec<NoncurrentTransition>,
+    pub noncurrent_expiration_days: Option<i64>,
+    pub abort_incomplete_multipart_upload_days: Option<i64>,
+}
+
+impl LifecycleRule {
+    pub fn new(id: &str, prefix: &str) -> LifecycleRule {
+        LifecycleRule {
+            id: id.to_string(),
+            enabled: true,
+            filter: LifecycleFilter { prefix: prefix.to_string(), tags: BTreeMap::new() },
+            transitions: vec![],
+            expiration: None,
+            expired_object_delete_marker: false,
+            noncurrent_transitions: vec![],
+            noncurrent_expiration_days: None,
+            abort_incomplete_multipart_upload_days: None,
+        }
+    }
+
+    pub fn from(r: &rusoto_s3::LifecycleRule) -> LifecycleRule {
+        let filter = match &r.filter {
+            Some(f) => match &f.and {
+                Some(and) => LifecycleFilter {
+                    prefix: and.prefix.clone().unwrap_or_default(),
+                    tags: and.tags.iter().flatten().map(|t| (t.key.clone(), t.value.clone())).collect(),
+                },
+                None => LifecycleFilter {
+                    prefix: f.prefix.clone().unwrap_or_default(),
+                    tags: f.tag.iter().map(|t| (t.key.clone(), t.value.clone())).collect(),
+                },
+            },
+            None => LifecycleFilter { prefix: r.prefix.clone().unwrap_or_default(), tags: BTreeMap::new() },
+        };
+        LifecycleRule {
+            id: r.id.clone().unwrap_or_default(),
+            enabled: r.status == "Enabled",
+            filter,
+            transitions: r.transitions.iter().flatten()
+                .filter_map(|t| LifecycleTiming::from(t.days, t.date.as_ref())
+                    .map(|timing| LifecycleTransition { timing, storage_class: t.storage_class.clone().unwrap_or_default() }))
+                .collect(),
+            expiration: r.expiration.as_ref().and_then(|e| LifecycleTiming::from(e.days, e.date.as_ref())),
+            expired_object_delete_marker: r.expiration.as_ref().and_then(|e| e.expired_object_delete_marker).unwrap_or(false),
+            noncurrent_transitions: r.noncurrent_version_transitions.iter().flatten()
+                .map(|t| NoncurrentTransition { noncurrent_days: t.noncurrent_days.unwrap_or(0), storage_class: t.storage_class.clone().unwrap_or_default() })
+                .collect(),
+            noncurrent_expiration_days: r.noncurrent_version_expiration.as_ref().and_then(|e| e.noncurrent_days),
+            abort_incomplete_multipart_upload_days: r.abort_incomplete_multipart_upload.as_ref().and_then(|a| a.days_after_initiation),
+        }
+    }
+
+    pub fn to_rusoto(&self) -> rusoto_s3::LifecycleRule {
+        let tags: Vec<rusoto_s3::Tag> = self.filter.tags.iter().map(|(key, value)| rusoto_s3::Tag { key: key.clone(), value: value.clone() }).collect();
+        let filter = if tags.is_empty() {
+            rusoto_s3::LifecycleRuleFilter { prefix: Some(self.filter.prefix.clone()), ..Default::default() }
+        } else {
+            rusoto_s3::LifecycleRuleFilter {
+                and: Some(rusoto_s3::LifecycleRuleAndOperator { prefix: Some(self.filter.prefix.clone()), tags: Some(tags) }),
+                ..Default::default()
+            }
+        };
+        let expiration = match (self.expiration, self.expired_object_delete_marker) {
+            (None, false) => None,
+            (timing, marker) => Some(rusoto_s3::LifecycleExpiration {
+                days: timing.and_then(|t| t.days()),
+                date: timing.and_then(|t| t.date()),
+                expired_object_delete_marker: if marker { Some(true) } else { None },
+            }),
+        };
+        rusoto_s3::LifecycleRule {
+            id: Some(self.id.clone()),
+            status: if self.enabled { "Enabled" } else { "Disabled" }.to_string(),
+            filter: Some(filter),
+            transitions: Some(self.transitions.iter().map(|t| rusoto_s3::Transition {
+                days: t.timing.days(),
+                date: t.timing.date(),
+                storage_class: Some(t.storage_class.clone()),
+            }).collect()).filter(|t: &Vec<rusoto_s3::Transition>| !t.is_empty()),
+            expiration,
+            noncurrent_version_transitions: Some(self.noncurrent_transitions.iter().map(|t| rusoto_s3::NoncurrentVersionTransition {
+                noncurrent_days: Some(t.noncurrent_days),
+                storage_class: Some(t.storage_class.clone()),
+            }).collect()).filter(|t: &Vec<rusoto_s3::NoncurrentVersionTransition>| !t.is_empty()),
+            noncurrent_version_expiration: self.noncurrent_expiration_days.map(|days| rusoto_s3::NoncurrentVersionExpiration { noncurrent_days: Some(days) }),
+            abort_incomplete_multipart_upload: self.abort_incomplete_multipart_upload_days.map(|days| rusoto_s3::AbortIncompleteMultipartUpload { days_after_initiation: Some(days) }),
+            ..Default::default()
+        }
+    }
+
+    fn has_action(&self) -> bool {
+        !self.transitions.is_empty() || self.expiration.is_some() || self.expired_object_delete_marker
+            || !self.noncurrent_transitions.is_empty() || self.noncurrent_expiration_days.is_some()
+            || self.abort_incomplete_multipart_upload_days.is_some()
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+        let name = if self.id.is_empty() { "rule without an id".to_string() } else { format!("rule {}", self.id) };
+
+        if self.id.len() > MAX_RULE_ID_LENGTH { problems.push(format!("{}: id is longer than {} characters", name, MAX_RULE_ID_LENGTH)); }
+        if !self.has_action() { problems.push(format!("{}: has no actions", name)); }
+        if self.expiration.is_some() && self.expired_object_delete_marker {
+            problems.push(format!("{}: expired object delete marker cannot be combined with an expiration", name));
+        }
+
+        let mut timings = self.transitions.iter().map(|t| &t.timing).chain(self.expiration.iter());
+        if let Some(first) = timings.next() {
+            if timings.any(|t| t.days().is_some() != first.days().is_some()) {
+                problems.push(format!("{}: transitions and expiration must all use days or all use dates", name));
+            }
+        }
+
+        let mut transitions: Vec<&LifecycleTransition> = self.transitions.iter().collect();
+        transitions.sort_by_key(|t| storage_class_rank(&t.storage_class));
+        for t in &transitions {
+            if storage_class_rank(&t.storage_class) == 0 {
+                problems.push(format!("{}: cannot transition to {}", name, t.storage_class));
+            }
+            if let Some(days) = t.timing.days() {
+                if days < 0 { problems.push(format!("{}: transition to {} has negative days", name, t.storage_class)); }
+                if ["STANDARD_IA", "ONEZONE_IA"].contains(&t.storage_class.as_str()) && days < MIN_INFREQUENT_ACCESS_DAYS {
+                    problems.push(format!("{}: transition to {} must be at least {} days after creation", name, t.storage_class, MIN_INFREQUENT_ACCESS_DAYS));
+                }
+            }
+        }
+        for pair in transitions.windows(2) {
+            if storage_class_rank(&pair[0].storage_class) == storage_class_rank(&pair[1].storage_class) {
+                problems.push(format!("{}: more than one transition to {}", name, pair[1].storage_class));
+            } else if pair[1].timing.due(epoch()) <= pair[0].timing.due(epoch()) {
+                problems.push(format!("{}: transition to {} must come after transition to {}", name, pair[1].storage_class, pair[0].storage_class));
+            }
+        }
+        if let Some(expiration) = self.expiration {
+            if matches!(expiration.days(), Some(days) if days < 1) {
+                problems.push(format!("{}: expiration must be at least 1 day after creation", name));
+            }
+            if transitions.iter().any(|t| expiration.due(epoch()) <= t.timing.due(epoch())) {
+                problems.push(format!("{}: expiration must come after every transition", name));
+            }
+        }
+        problems
+    }
+
+    /// Whether the rule applies to an object, judged from its key alone: rules
+    /// that filter on tags never match, since a listing carries no tags.
+    fn matches(&self, object: &S3ListObject) -> bool {
+        self.enabled && self.filter.tags.is_empty() && object.key.starts_with(&self.filter.prefix)
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Default)]
+pub struct LifecycleConfiguration {
+    pub rules: Vec<LifecycleRule>,
+}
+
+impl LifecycleConfiguration {
+    pub fn from(rules: &[rusoto_s3::LifecycleRule]) -> LifecycleConfiguration {
+        LifecycleConfiguration { rules: rules.iter().map(LifecycleRule::from).collect() }
+    }
+
+    pub fn to_rusoto(&self) -> rusoto_s3::BucketLifecycleConfiguration {
+        rusoto_s3::BucketLifecycleConfiguration { rules: self.rules.iter().map(LifecycleRule::to_rusoto).collect() }
+    }
+
+    /// Every reason S3 would reject the configuration, or the most common
+    /// mistakes it would accept but almost certainly not mean.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = vec![];
+        if self.rules.len() > MAX_LIFECYCLE_RULES {
+            problems.push(format!("{} rules, at most {} are allowed", self.rules.len(), MAX_LIFECYCLE_RULES));
+        }
+        let mut ids = HashSet::new();
+        for rule in &self.rules {
+            if !rule.id.is_empty() && !ids.insert(rule.id.as_str()) {
+                problems.push(format!("rule {}: id is used more than once", rule.id));
+            }
+            problems.extend(rule.problems());
+        }
+        if problems.is_empty() { Ok(()) } else { Err(problems) }
+    }
+
+    /// What the rules will have done to each listed object by `as_of`: expired,
+    /// or moved to the coldest storage class it has reached. Objects the rules
+    /// leave alone are not included.
+    pub fn simulate(&self, objects: &[S3ListObject], as_of: DateTime<Utc>) -> Vec<LifecyclePrediction> {
+        objects.iter().filter_map(|object| {
+            let created = object.last_modified_date_time()?;
+            let rules: Vec<&LifecycleRule> = self.rules.iter().filter(|r| r.matches(object)).collect();
+
+            let expiry = rules.iter()
+                .filter_map(|r| r.expiration.map(|e| (r, e.due(created))))
+                .filter(|(_, due)| *due <= as_of)
+                .min_by_key(|(_, due)| *due);
+            if let Some((rule, due)) = expiry {
+                return Some(LifecyclePrediction { key: object.key.clone(), rule_id: rule.id.clone(), action: LifecycleAction::Expire, due });
+            }
+
+            let current_rank = storage_class_rank(object.storage_class.as_deref().unwrap_or("STANDARD"));
+            rules.iter()
+                .flat_map(|r| r.transitions.iter().map(move |t| (r, t, t.timing.due(created))))
+                .filter(|(_, t, due)| *due <= as_of && storage_class_rank(&t.storage_class) > current_rank)
+                .max_by_key(|(_, t, _)| storage_class_rank(&t.storage_class))
+                .map(|(rule, t, due)| LifecyclePrediction {
+                    key: object.key.clone(),
+                    rule_id: rule.id.clone(),
+                    action: LifecycleAction::Transition { storage_class: t.storage_class.clone() },
+                    due,
+                })
+        }).collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum LifecycleAction {
+    Transition { storage_class: String },
+    Expire,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct LifecyclePrediction {
+    pub key: String,
+    pub rule_id: String,
+    pub action: LifecycleAction,
+    pub due: DateTime<Utc>,
+}
+
+fn epoch() -> DateTime<Utc> {
+    Utc.timestamp_opt(0, 0).unwrap()
+}
+
+#[cfg(test)]
+fn date(rfc3339: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+}
+
+#[cfg(test)]
+fn object(key: &str, last_modified: &str, storage_class: &str) -> S3ListObject {
+    S3ListObject { last_modified: last_modified.to_string(), size: 1, key: key.to_string(), storage_class: Some(storage_class.to_string()), e_tag: None }
+}
+
+#[test]
+fn days_round_up_to_next_midnight() {
+    assert_eq!(date("2020-01-03T00:00:00Z"), LifecycleTiming::Days(1).due(date("2020-01-01T10:30:00Z")));
+    assert_eq!(date("2020-01-02T00:00:00Z"), LifecycleTiming::Days(1).due(date("2020-01-01T00:00:00Z")));
+}
+
+#[test]
+fn validation_reports_each_problem() {
+    let mut rule = LifecycleRule::new("logs", "logs/");
+    rule.transitions = vec![
+        LifecycleTransition { timing: LifecycleTiming::Days(10), storage_class: "STANDARD_IA".to_string() },
+        LifecycleTransition { timing: LifecycleTiming::Days(5), storage_class: "GLACIER".to_string() },
+    ];
+    rule.expiration = Some(LifecycleTiming::Days(7));
+    let config = LifecycleConfiguration { rules: vec![rule, LifecycleRule::new("logs", "other/")] };
+
+    assert_eq!(Err(vec![
+        "rule logs: transition to STANDARD_IA must be at least 30 days after creation".to_string(),
+        "rule logs: transition to GLACIER must come after transition to STANDARD_IA".to_string(),
+        "rule logs: expiration must come after every transition".to_string(),
+        "rule logs: id is used more than once".to_string(),
+        "rule logs: has no actions".to_string(),
+    ]), config.validate());
+}
+
+#[test]
+fn simulation_predicts_transitions_and_expiry() {
+    let mut archive = LifecycleRule::new("archive", "data/");
+    archive.transitions = vec![
+        LifecycleTransition { timing: LifecycleTiming::Days(30), storage_class: "STANDARD_IA".to_string() },
+        LifecycleTransition { timing: LifecycleTiming::Days(90), storage_class: "GLACIER".to_string() },
+    ];
+    let mut expire = LifecycleRule::new("expire", "data/tmp/");
+    expire.expiration = Some(LifecycleTiming::Days(1));
+    let config = LifecycleConfiguration { rules: vec![archive, expire] };
+    assert!(config.validate().is_ok());
+
+    let objects = vec![
+        object("data/old", "2020-01-01T00:00:00.000Z", "STANDARD"),
+        object("data/recent", "2020-03-01T00:00:00.000Z", "STANDARD"),
+        object("data/new", "2020-04-20T00:00:00.000Z", "STANDARD"),
+        object("data/tmp/scratch", "2020-04-20T00:00:00.000Z", "STANDARD"),
+        object("other/old", "2020-01-01T00:00:00.000Z", "STANDARD"),
+    ];
+    let predictions = config.simulate(&objects, date("2020-05-01T00:00:00Z"));
+
+    assert_eq!(vec![
+        ("data/old", LifecycleAction::Transition { storage_class: "GLACIER".to_string() }),
+        ("data/recent", LifecycleAction::Transition { storage_class: "STANDARD_IA".to_string() }),
+        ("data/tmp/scratch", LifecycleAction::Expire),
+    ], predictions.iter().map(|p| (p.key.as_str(), p.action.clone())).collect::<Vec<(&str, LifecycleAction)>>());
+    assert_eq!(date("2020-03-31T00:00:00Z"), predictions[1].due);
+}
@@ -1,2 +1,22 @@
 pub mod s3_list_object;
-pub mod s3_location;
\ No newline at end of file
+pub mod s3_location;
+pub mod s3_location_error;
+pub mod object_filter;
+pub mod disk_usage;
+pub mod etag;
+pub mod download;
+pub mod upload;
+pub mod operation;
+pub mod sync;
+pub mod object_metadata;
+pub mod s3_object_version;
+pub mod restore;
+pub mod presign;
+pub mod partition;
+pub mod output_check;
+pub mod diff;
+pub mod select;
+pub mod inventory;
+pub mod bucket;
+pub mod lifecycle;
+pub mod multipart_upload;
@@ -0,0 +1,125 @@
+use chrono::{DateTime, Duration, Utc};
+use serde_derive::Serialize;
+use crate::s3::models::operation::OperationFailure;
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct S3MultipartUpload {
+    pub key: String,
+    pub upload_id: String,
+    pub initiated: String,
+    pub storage_class: Option<String>,
+    pub initiator: Option<String>,
+    /// Parts uploaded so far, and their total size in bytes.
+    pub parts: usize,
+    pub size: i64,
+}
+
+impl S3MultipartUpload {
+    pub fn from(u: &rusoto_s3::MultipartUpload) -> S3MultipartUpload {
+        S3MultipartUpload {
+            key: u.key.clone().unwrap_or_default(),
+            upload_id: u.upload_id.clone().unwrap_or_default(),
+            initiated: u.initiated.clone().unwrap_or_default(),
+            storage_class: u.storage_class.clone(),
+            initiator: u.initiator.as_ref().and_then(|i| i.display_name.clone().or_else(|| i.id.clone())),
+            parts: 0,
+            size: 0,
+        }
+    }
+
+    pub fn with_parts(self, parts: &[rusoto_s3::Part]) -> S3MultipartUpload {
+        S3MultipartUpload {
+            parts: parts.len(),
+            size: parts.iter().map(|part| part.size.unwrap_or(0)).sum(),
+            ..self
+        }
+    }
+
+    pub fn initiated_date_time(&self) -> Option<DateTime<Utc>> {
+        match DateTime::parse_from_rfc3339(&self.initiated) {
+            Ok(date_time) => Some(date_time.with_timezone(&Utc)),
+            Err(_e) => None,
+        }
+    }
+
+    /// Uploads whose start time can't be read are never considered stale.
+    pub fn is_older_than(&self, age: Duration, now: DateTime<Utc>) -> bool {
+        matches!(self.initiated_date_time(), Some(initiated) if now - initiated > age)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AbortOptions {
+    pub dry_run: bool,
+    pub concurrency: usize,
+}
+
+impl Default for AbortOptions {
+    fn default() -> AbortOptions {
+        AbortOptions {
+            dry_run: false,
+            concurrency: 8,
+        }
+    }
+}
+
+/// `parts` and `size` total the planned uploads, which is what a dry run frees.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct AbortReport {
+    pub dry_run: bool,
+    pub planned: Vec<S3MultipartUpload>,
+    pub parts: usize,
+    pub size: i64,
+    pub aborted: usize,
+    pub failures: Vec<OperationFailure>,
+}
+
+impl AbortReport {
+    pub fn from(planned: Vec<S3MultipartUpload>, dry_run: bool) -> AbortReport {
+        AbortReport {
+            dry_run,
+            parts: planned.iter().map(|upload| upload.parts).sum(),
+            size: planned.iter().map(|upload| upload.size).sum(),
+            planned,
+            aborted: 0,
+            failures: vec![],
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[test]
+fn uploads_are_stale_after_the_given_age() {
+    let upload = S3MultipartUpload {
+        key: "k".to_string(),
+        upload_id: "u".to_string(),
+        initiated: "2020-01-01T00:00:00.000Z".to_string(),
+        storage_class: None,
+        initiator: None,
+        parts: 0,
+        size: 0,
+    };
+    let now = DateTime::parse_from_rfc3339("2020-01-08T00:00:00Z").unwrap().with_timezone(&Utc);
+    assert!(upload.is_older_than(Duration::days(6), now));
+    assert!(!upload.is_older_than(Duration::days(7), now));
+    assert!(!S3MultipartUpload { initiated: String::new(), ..upload }.is_older_than(Duration::days(1), now));
+}
+
+#[test]
+fn reports_total_the_planned_parts() {
+    let part = |size| rusoto_s3::Part { size: Some(size), ..rusoto_s3::Part::default() };
+    let upload = S3MultipartUpload::from(&rusoto_s3::MultipartUpload::default());
+    let report = AbortReport::from(vec![
+        upload.clone().with_parts(&[part(5), part(3)]),
+        upload.with_parts(&[part(2)]),
+    ], true);
+    assert_eq!((2, 8), (report.planned[0].parts, report.planned[0].size));
+    assert_eq!((3, 10), (report.parts, report.size));
+}
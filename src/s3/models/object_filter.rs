@@ -0,0 +1,217 @@
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use crate::s3::models::s3_list_object::S3ListObject;
+
+/// Client-side predicate applied to objects as they are listed.
+///
+/// Every criterion that is set must match for an object to be kept; an empty
+/// filter keeps everything. Glob patterns are matched against the full key,
+/// where `*` and `?` stay within one path segment and `**` spans segments.
+#[derive(Clone, Debug, Default)]
+pub struct ObjectFilter {
+    globs: Vec<Regex>,
+    regexes: Vec<Regex>,
+    min_size: Option<i64>,
+    max_size: Option<i64>,
+    modified_after: Option<DateTime<Utc>>,
+    modified_before: Option<DateTime<Utc>>,
+    storage_classes: Vec<String>,
+}
+
+impl ObjectFilter {
+    pub fn new() -> ObjectFilter {
+        ObjectFilter::default()
+    }
+
+    pub fn glob(mut self, pattern: &str) -> Result<ObjectFilter, String> {
+        match Regex::new(&glob_to_regex(pattern)) {
+            Ok(re) => {
+                self.globs.push(re);
+                Ok(self)
+            },
+            Err(e) => Err(format!("Invalid glob '{}': {}", pattern, e)),
+        }
+    }
+
+    pub fn regex(mut self, pattern: &str) -> Result<ObjectFilter, String> {
+        match Regex::new(pattern) {
+            Ok(re) => {
+                self.regexes.push(re);
+                Ok(self)
+            },
+            Err(e) => Err(format!("Invalid regex '{}': {}", pattern, e)),
+        }
+    }
+
+    pub fn min_size(mut self, bytes: i64) -> ObjectFilter {
+        self.min_size = Some(bytes);
+        self
+    }
+
+    pub fn max_size(mut self, bytes: i64) -> ObjectFilter {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    pub fn modified_after(mut self, date_time: DateTime<Utc>) -> ObjectFilter {
+        self.modified_after = Some(date_time);
+        self
+    }
+
+    pub fn modified_before(mut self, date_time: DateTime<Utc>) -> ObjectFilter {
+        self.modified_before = Some(date_time);
+        self
+    }
+
+    pub fn storage_class(mut self, storage_class: &str) -> ObjectFilter {
+        self.storage_classes.push(storage_class.to_uppercase());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.globs.is_empty() &&
+            self.regexes.is_empty() &&
+            self.min_size.is_none() &&
+            self.max_size.is_none() &&
+            self.modified_after.is_none() &&
+            self.modified_before.is_none() &&
+            self.storage_classes.is_empty()
+    }
+
+    pub fn matches(&self, object: &S3ListObject) -> bool {
+        if !self.globs.is_empty() && !self.globs.iter().any(|re| re.is_match(&object.key)) { return false; }
+        if !self.regexes.iter().all(|re| re.is_match(&object.key)) { return false; }
+        if matches!(self.min_size, Some(min) if object.size < min) { return false; }
+        if matches!(self.max_size, Some(max) if object.size > max) { return false; }
+        if self.modified_after.is_some() || self.modified_before.is_some() {
+            match object.last_modified_date_time() {
+                Some(last_modified) => {
+                    if matches!(self.modified_after, Some(after) if last_modified < after) { return false; }
+                    if matches!(self.modified_before, Some(before) if last_modified >= before) { return false; }
+                },
+                None => return false,
+            }
+        }
+        if !self.storage_classes.is_empty() {
+            let storage_class = object.storage_class.clone().unwrap_or_else(|| "STANDARD".to_string());
+            if !self.storage_classes.contains(&storage_class) { return false; }
+        }
+        true
+    }
+}
+
+pub fn glob_to_regex(pattern: &str) -> String {
+    let chars = pattern.chars().collect::<Vec<char>>();
+    let mut re = String::from("^");
+    let mut in_alternation = false;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' if i + 1 < chars.len() && chars[i + 1] == '*' => {
+                if i + 2 < chars.len() && chars[i + 2] == '/' {
+                    re.push_str("(?:.*/)?");
+                    i += 2;
+                } else {
+                    re.push_str(".*");
+                    i += 1;
+                }
+            },
+            '*' => re.push_str("[^/]*"),
+            '?' => re.push_str("[^/]"),
+            '[' => match chars[i..].iter().position(|c| *c == ']') {
+                Some(end) if end > 1 => {
+                    let class = chars[i + 1..i + end].iter().collect::<String>();
+                    re.push('[');
+                    match class.strip_prefix('!') {
+                        Some(negated) => { re.push('^'); re.push_str(&negated.replace('\\', "\\\\")); },
+                        None => re.push_str(&class.replace('\\', "\\\\")),
+                    }
+                    re.push(']');
+                    i += end;
+                },
+                _ => re.push_str("\\["),
+            },
+            '{' => { in_alternation = true; re.push_str("(?:"); },
+            '}' if in_alternation => { in_alternation = false; re.push(')'); },
+            ',' if in_alternation => re.push('|'),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    re.push('$');
+    re
+}
+
+#[cfg(test)]
+fn test_object(key: &str, size: i64, last_modified: &str, storage_class: &str) -> S3ListObject {
+    S3ListObject {
+        last_modified: last_modified.to_string(),
+        size,
+        key: key.to_string(),
+        storage_class: Some(storage_class.to_string()),
+        e_tag: None,
+    }
+}
+
+#[test]
+fn empty_filter_matches_everything() {
+    let filter = ObjectFilter::new();
+    assert!(filter.is_empty());
+    assert!(filter.matches(&test_object("a/b/c.csv", 0, "2020-01-01T00:00:00.000Z", "STANDARD")));
+}
+
+#[test]
+fn glob_double_star_spans_directories() {
+    let filter = ObjectFilter::new().glob("table/**/*.parquet").unwrap();
+    assert!(filter.matches(&test_object("table/dt=2020-01-01/part-0.parquet", 1, "2020-01-01T00:00:00.000Z", "STANDARD")));
+    assert!(filter.matches(&test_object("table/part-0.parquet", 1, "2020-01-01T00:00:00.000Z", "STANDARD")));
+    assert!(!filter.matches(&test_object("table/dt=2020-01-01/part-0.csv", 1, "2020-01-01T00:00:00.000Z", "STANDARD")));
+    assert!(!filter.matches(&test_object("other/part-0.parquet", 1, "2020-01-01T00:00:00.000Z", "STANDARD")));
+}
+
+#[test]
+fn glob_single_star_stays_within_segment() {
+    let filter = ObjectFilter::new().glob("table/*.parquet").unwrap();
+    assert!(filter.matches(&test_object("table/part-0.parquet", 1, "2020-01-01T00:00:00.000Z", "STANDARD")));
+    assert!(!filter.matches(&test_object("table/dt=1/part-0.parquet", 1, "2020-01-01T00:00:00.000Z", "STANDARD")));
+}
+
+#[test]
+fn glob_supports_classes_and_alternation() {
+    assert_eq!("^a/[^0-9]\\.(?:csv|json)$", glob_to_regex("a/[!0-9].{csv,json}"));
+    let filter = ObjectFilter::new().glob("**/part-?.{csv,json}").unwrap();
+    assert!(filter.matches(&test_object("x/part-1.json", 1, "2020-01-01T00:00:00.000Z", "STANDARD")));
+    assert!(!filter.matches(&test_object("x/part-10.json", 1, "2020-01-01T00:00:00.000Z", "STANDARD")));
+}
+
+#[test]
+fn size_range_is_inclusive() {
+    let filter = ObjectFilter::new().min_size(10).max_size(20);
+    assert!(!filter.matches(&test_object("a", 9, "2020-01-01T00:00:00.000Z", "STANDARD")));
+    assert!(filter.matches(&test_object("a", 10, "2020-01-01T00:00:00.000Z", "STANDARD")));
+    assert!(filter.matches(&test_object("a", 20, "2020-01-01T00:00:00.000Z", "STANDARD")));
+    assert!(!filter.matches(&test_object("a", 21, "2020-01-01T00:00:00.000Z", "STANDARD")));
+}
+
+#[test]
+fn modified_range_filters_on_last_modified() {
+    let after = DateTime::parse_from_rfc3339("2020-01-02T00:00:00Z").unwrap().with_timezone(&Utc);
+    let before = DateTime::parse_from_rfc3339("2020-01-03T00:00:00Z").unwrap().with_timezone(&Utc);
+    let filter = ObjectFilter::new().modified_after(after).modified_before(before);
+    assert!(!filter.matches(&test_object("a", 1, "2020-01-01T23:59:59.000Z", "STANDARD")));
+    assert!(filter.matches(&test_object("a", 1, "2020-01-02T12:00:00.000Z", "STANDARD")));
+    assert!(!filter.matches(&test_object("a", 1, "2020-01-03T00:00:00.000Z", "STANDARD")));
+}
+
+#[test]
+fn storage_class_and_regex_filters_combine() {
+    let filter = ObjectFilter::new().storage_class("glacier").regex(r"\d{4}").unwrap();
+    assert!(filter.matches(&test_object("log-2020", 1, "2020-01-01T00:00:00.000Z", "GLACIER")));
+    assert!(!filter.matches(&test_object("log-2020", 1, "2020-01-01T00:00:00.000Z", "STANDARD")));
+    assert!(!filter.matches(&test_object("log", 1, "2020-01-01T00:00:00.000Z", "GLACIER")));
+}
+
+#[test]
+fn invalid_regex_is_an_error() {
+    assert!(ObjectFilter::new().regex("(unclosed").is_err());
+}
@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde_derive::Serialize;
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct RestoreStatus {
+    pub ongoing: bool,
+    pub expiry: Option<DateTime<Utc>>,
+}
+
+impl RestoreStatus {
+    /// Parses the `x-amz-restore` header, e.g.
+    /// `ongoing-request="false", expiry-date="Fri, 21 Dec 2012 00:00:00 GMT"`.
+    pub fn from(header: &str) -> Option<RestoreStatus> {
+        let ongoing = Regex::new(r#"ongoing-request="(?P<ongoing>true|false)""#).unwrap()
+            .captures(header)
+            .map(|cap| &cap["ongoing"] == "true")?;
+        let expiry = Regex::new(r#"expiry-date="(?P<expiry>[^"]+)""#).unwrap()
+            .captures(header)
+            .and_then(|cap| parse_http_date(&cap["expiry"]));
+        Some(RestoreStatus { ongoing, expiry })
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ObjectMetadata {
+    pub bucket: String,
+    pub key: String,
+    pub content_length: i64,
+    pub content_type: Option<String>,
+    pub e_tag: Option<String>,
+    pub last_modified: Option<DateTime<Utc>>,
+    pub storage_class: String,
+    pub server_side_encryption: Option<String>,
+    pub kms_key_id: Option<String>,
+    pub version_id: Option<String>,
+    pub metadata: BTreeMap<String, String>,
+    pub restore: Option<RestoreStatus>,
+}
+
+impl ObjectMetadata {
+    pub fn is_archived(&self) -> bool {
+        self.storage_class == "GLACIER" || self.storage_class == "DEEP_ARCHIVE"
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// HEAD responses carry RFC 2822 style dates such as `Wed, 21 Oct 2015 07:28:00 GMT`.
+pub fn parse_http_date(date: &str) -> Option<DateTime<Utc>> {
+    match DateTime::parse_from_rfc2822(date) {
+        Ok(date_time) => Some(date_time.with_timezone(&Utc)),
+        Err(_e) => None,
+    }
+}
+
+#[test]
+fn completed_restore_header_is_parsed() {
+    let actual = RestoreStatus::from("ongoing-request=\"false\", expiry-date=\"Fri, 21 Dec 2012 00:00:00 GMT\"").unwrap();
+    assert!(!actual.ongoing);
+    assert_eq!("2012-12-21T00:00:00+00:00", actual.expiry.unwrap().to_rfc3339());
+}
+
+#[test]
+fn ongoing_restore_header_is_parsed() {
+    let actual = RestoreStatus::from("ongoing-request=\"true\"").unwrap();
+    assert!(actual.ongoing);
+    assert!(actual.expiry.is_none());
+}
+
+#[test]
+fn unrecognised_restore_header_is_none() {
+    assert!(RestoreStatus::from("").is_none());
+}
+
+#[test]
+fn http_dates_are_parsed() {
+    assert_eq!("2015-10-21T07:28:00+00:00", parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT").unwrap().to_rfc3339());
+    assert!(parse_http_date("2015-10-21").is_none());
+}
@@ -0,0 +1,70 @@
+use serde_derive::Serialize;
+
+#[derive(Clone, Debug)]
+pub struct OperationOptions {
+    pub recursive: bool,
+    pub dry_run: bool,
+    pub concurrency: usize,
+    pub copy_part_size: u64,
+}
+
+impl Default for OperationOptions {
+    fn default() -> OperationOptions {
+        OperationOptions {
+            recursive: false,
+            dry_run: false,
+            concurrency: 8,
+            copy_part_size: 512 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub enum PlannedOperation {
+    Copy {
+        source_bucket: String,
+        source_key: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        source_version_id: Option<String>,
+        destination_bucket: String,
+        destination_key: String,
+        size: i64,
+    },
+    Delete {
+        bucket: String,
+        key: String,
+    },
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct OperationFailure {
+    pub bucket: String,
+    pub key: String,
+    pub code: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct OperationReport {
+    pub dry_run: bool,
+    pub planned: Vec<PlannedOperation>,
+    pub completed: usize,
+    pub failures: Vec<OperationFailure>,
+}
+
+impl OperationReport {
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[test]
+fn planned_operations_serialize_with_operation_tag() {
+    let delete = PlannedOperation::Delete { bucket: "b".to_string(), key: "k".to_string() };
+    assert_eq!("{\"operation\":\"delete\",\"bucket\":\"b\",\"key\":\"k\"}", serde_json::to_string(&delete).unwrap());
+}
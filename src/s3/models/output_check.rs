@@ -0,0 +1,135 @@
+use chrono::{DateTime, Duration, Utc};
+use serde_derive::Serialize;
+use crate::s3::models::s3_list_object::S3ListObject;
+
+pub const SUCCESS_MARKER: &str = "_SUCCESS";
+pub const TEMPORARY_DIRECTORY: &str = "_temporary";
+
+#[derive(Clone, Debug)]
+pub struct OutputRules {
+    pub success_marker: Option<String>,
+    pub min_objects: Option<usize>,
+    pub min_bytes: Option<i64>,
+    pub forbid_temporary: bool,
+    pub max_age: Option<Duration>,
+}
+
+impl Default for OutputRules {
+    fn default() -> OutputRules {
+        OutputRules {
+            success_marker: Some(SUCCESS_MARKER.to_string()),
+            min_objects: None,
+            min_bytes: None,
+            forbid_temporary: true,
+            max_age: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "check", rename_all = "snake_case")]
+pub enum OutputCheckFailure {
+    MissingSuccessMarker { marker: String },
+    TooFewObjects { expected: usize, actual: usize },
+    TooFewBytes { expected: i64, actual: i64 },
+    TemporaryObjects { count: usize, example: String },
+    Stale { newest: Option<DateTime<Utc>>, max_age_seconds: i64 },
+}
+
+/// Whether an output prefix is complete, with every rule it failed.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct OutputVerdict {
+    pub location: String,
+    pub complete: bool,
+    pub objects: usize,
+    pub bytes: i64,
+    pub newest: Option<DateTime<Utc>>,
+    pub failures: Vec<OutputCheckFailure>,
+}
+
+impl OutputVerdict {
+    /// Judges a listing of `prefix` against the rules. The marker and anything
+    /// under a `_temporary` directory are not counted as output.
+    pub fn evaluate(location: &str, prefix: &str, objects: &[S3ListObject], rules: &OutputRules, now: DateTime<Utc>) -> OutputVerdict {
+        let is_marker = |o: &S3ListObject| matches!(&rules.success_marker, Some(m) if relative(o, prefix) == m);
+        let is_temporary = |o: &S3ListObject| relative(o, prefix).split('/').any(|s| s == TEMPORARY_DIRECTORY);
+        let marker = objects.iter().find(|o| is_marker(o));
+        let temporary: Vec<&S3ListObject> = objects.iter().filter(|o| is_temporary(o)).collect();
+        let output: Vec<&S3ListObject> = objects.iter().filter(|o| !is_marker(o) && !is_temporary(o)).collect();
+
+        let count = output.len();
+        let bytes: i64 = output.iter().map(|o| o.size).sum();
+        let newest = marker.into_iter().chain(output.iter().copied()).filter_map(|o| o.last_modified_date_time()).max();
+
+        let mut failures = vec![];
+        if let (Some(expected), None) = (&rules.success_marker, marker) {
+            failures.push(OutputCheckFailure::MissingSuccessMarker { marker: expected.clone() });
+        }
+        if let Some(expected) = rules.min_objects.filter(|expected| count < *expected) {
+            failures.push(OutputCheckFailure::TooFewObjects { expected, actual: count });
+        }
+        if let Some(expected) = rules.min_bytes.filter(|expected| bytes < *expected) {
+            failures.push(OutputCheckFailure::TooFewBytes { expected, actual: bytes });
+        }
+        if rules.forbid_temporary && !temporary.is_empty() {
+            failures.push(OutputCheckFailure::TemporaryObjects { count: temporary.len(), example: temporary[0].key.clone() });
+        }
+        if let Some(max_age) = rules.max_age {
+            if !matches!(newest, Some(n) if now - n <= max_age) {
+                failures.push(OutputCheckFailure::Stale { newest, max_age_seconds: max_age.num_seconds() });
+            }
+        }
+
+        OutputVerdict { location: location.to_string(), complete: failures.is_empty(), objects: count, bytes, newest, failures }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+fn relative<'a>(object: &'a S3ListObject, prefix: &str) -> &'a str {
+    object.key.strip_prefix(prefix).unwrap_or(object.key.as_str())
+}
+
+#[cfg(test)]
+fn object(key: &str, size: i64, last_modified: &str) -> S3ListObject {
+    S3ListObject { last_modified: last_modified.to_string(), size, key: key.to_string(), storage_class: None, e_tag: None }
+}
+
+#[cfg(test)]
+fn now() -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339("2020-01-02T00:00:00Z").unwrap().with_timezone(&Utc)
+}
+
+#[test]
+fn complete_output_passes_every_rule() {
+    let objects = vec![
+        object("out/part-0", 100, "2020-01-01T23:00:00.000Z"),
+        object("out/_SUCCESS", 0, "2020-01-01T23:30:00.000Z"),
+    ];
+    let rules = OutputRules { min_objects: Some(1), min_bytes: Some(100), max_age: Some(Duration::hours(1)), ..OutputRules::default() };
+    let verdict = OutputVerdict::evaluate("s3://b/out/", "out/", &objects, &rules, now());
+
+    assert!(verdict.complete);
+    assert_eq!(1, verdict.objects);
+    assert_eq!(Some(now() - Duration::minutes(30)), verdict.newest);
+}
+
+#[test]
+fn incomplete_output_reports_each_failure() {
+    let objects = vec![
+        object("out/part-0", 10, "2020-01-01T00:00:00.000Z"),
+        object("out/_temporary/0/part-1", 10, "2020-01-01T00:00:00.000Z"),
+    ];
+    let rules = OutputRules { min_objects: Some(2), max_age: Some(Duration::hours(1)), ..OutputRules::default() };
+    let verdict = OutputVerdict::evaluate("s3://b/out/", "out/", &objects, &rules, now());
+
+    assert!(!verdict.complete);
+    assert_eq!(vec![
+        OutputCheckFailure::MissingSuccessMarker { marker: "_SUCCESS".to_string() },
+        OutputCheckFailure::TooFewObjects { expected: 2, actual: 1 },
+        OutputCheckFailure::TemporaryObjects { count: 1, example: "out/_temporary/0/part-1".to_string() },
+        OutputCheckFailure::Stale { newest: Some(now() - Duration::days(1)), max_age_seconds: 3600 },
+    ], verdict.failures);
+}
@@ -0,0 +1,179 @@
+use std::collections::BTreeMap;
+use chrono::{Duration, NaiveDate, NaiveDateTime, Timelike};
+use serde_derive::Serialize;
+use crate::s3::models::s3_list_object::S3ListObject;
+
+/// A partition value, typed from its text: ISO dates, then integers, then strings.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(untagged)]
+pub enum PartitionValue {
+    Date(NaiveDate),
+    Integer(i64),
+    String(String),
+}
+
+impl PartitionValue {
+    pub fn parse(raw: &str) -> PartitionValue {
+        if let Ok(date) = NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+            PartitionValue::Date(date)
+        } else if let Ok(integer) = raw.parse::<i64>() {
+            PartitionValue::Integer(integer)
+        } else {
+            PartitionValue::String(raw.to_string())
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PartitionColumn {
+    pub name: String,
+    pub value: PartitionValue,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Partition {
+    pub prefix: String,
+    pub columns: Vec<PartitionColumn>,
+    pub objects: usize,
+    pub bytes: i64,
+}
+
+impl Partition {
+    pub fn value(&self, name: &str) -> Option<&PartitionValue> {
+        self.columns.iter().find(|c| c.name == name).map(|c| &c.value)
+    }
+
+    /// The start of the period this partition covers, from a date column and an
+    /// optional integer hour column.
+    pub fn time(&self, date_column: &str, hour_column: Option<&str>) -> Option<NaiveDateTime> {
+        let date = match self.value(date_column) {
+            Some(PartitionValue::Date(date)) => *date,
+            _ => return None,
+        };
+        match hour_column.map(|hour| self.value(hour)) {
+            None => date.and_hms_opt(0, 0, 0),
+            Some(Some(PartitionValue::Integer(hour))) if (0..24).contains(hour) => date.and_hms_opt(*hour as u32, 0, 0),
+            Some(_) => None,
+        }
+    }
+
+    fn sort_key(&self) -> Vec<&PartitionValue> {
+        self.columns.iter().map(|c| &c.value).collect()
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Default)]
+pub struct PartitionSet {
+    pub table_prefix: String,
+    pub columns: Vec<String>,
+    pub partitions: Vec<Partition>,
+}
+
+impl PartitionSet {
+    /// Groups a listing of `table_prefix` by the `name=value` directories that
+    /// directly follow it. Objects outside any partition are ignored.
+    pub fn discover(table_prefix: &str, objects: &[S3ListObject]) -> PartitionSet {
+        let mut partitions: BTreeMap<String, Partition> = BTreeMap::new();
+        for object in objects {
+            let relative = object.key.strip_prefix(table_prefix).unwrap_or(object.key.as_str());
+            let columns = partition_columns(relative);
+            if columns.is_empty() { continue; }
+
+            let depth = columns.len();
+            let prefix = format!("{}{}/", table_prefix, relative.split('/').take(depth).collect::<Vec<&str>>().join("/"));
+            let partition = partitions.entry(prefix.clone()).or_insert_with(|| Partition { prefix, columns, objects: 0, bytes: 0 });
+            partition.objects += 1;
+            partition.bytes += object.size;
+        }
+
+        let mut partitions: Vec<Partition> = partitions.into_values().collect();
+        partitions.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+        let columns = partitions.iter()
+            .max_by_key(|p| p.columns.len())
+            .map(|p| p.columns.iter().map(|c| c.name.clone()).collect())
+            .unwrap_or_default();
+        PartitionSet { table_prefix: table_prefix.to_string(), columns, partitions }
+    }
+
+    pub fn latest(&self) -> Option<&Partition> {
+        self.partitions.last()
+    }
+
+    /// Every day (or hour, with an hour column) in `from..=to` that has no
+    /// partition.
+    pub fn gaps(&self, date_column: &str, hour_column: Option<&str>, from: NaiveDateTime, to: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let step = if hour_column.is_some() { Duration::hours(1) } else { Duration::days(1) };
+        let present: Vec<NaiveDateTime> = self.partitions.iter().filter_map(|p| p.time(date_column, hour_column)).collect();
+
+        let mut gaps = vec![];
+        let hour = if hour_column.is_some() { from.hour() } else { 0 };
+        let mut slot = match from.date().and_hms_opt(hour, 0, 0) {
+            Some(slot) => slot,
+            None => return vec![],
+        };
+        while slot <= to {
+            if !present.contains(&slot) { gaps.push(slot); }
+            slot += step;
+        }
+        gaps
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// The leading `name=value` directories of a key relative to the table root.
+pub fn partition_columns(relative_key: &str) -> Vec<PartitionColumn> {
+    let segments: Vec<&str> = relative_key.split('/').collect();
+    segments[..segments.len() - 1].iter()
+        .map_while(|segment| match segment.split_once('=') {
+            Some((name, value)) if !name.is_empty() => Some(PartitionColumn { name: name.to_string(), value: PartitionValue::parse(value) }),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+fn object(key: &str) -> S3ListObject {
+    S3ListObject { last_modified: String::new(), size: 10, key: key.to_string(), storage_class: None, e_tag: None }
+}
+
+#[test]
+fn partition_values_are_typed() {
+    assert_eq!(PartitionValue::Date(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()), PartitionValue::parse("2024-01-01"));
+    assert_eq!(PartitionValue::Integer(3), PartitionValue::parse("03"));
+    assert_eq!(PartitionValue::String("eu".to_string()), PartitionValue::parse("eu"));
+}
+
+#[test]
+fn discovers_partitions_from_keys() {
+    let objects = vec![
+        object("table/dt=2024-01-02/hour=00/part-0.parquet"),
+        object("table/dt=2024-01-01/hour=10/part-0.parquet"),
+        object("table/dt=2024-01-01/hour=3/part-0.parquet"),
+        object("table/dt=2024-01-01/hour=3/part-1.parquet"),
+        object("table/_SUCCESS"),
+    ];
+    let set = PartitionSet::discover("table/", &objects);
+
+    assert_eq!(vec!["dt".to_string(), "hour".to_string()], set.columns);
+    assert_eq!(3, set.partitions.len());
+    assert_eq!("table/dt=2024-01-01/hour=3/", set.partitions[0].prefix);
+    assert_eq!(2, set.partitions[0].objects);
+    assert_eq!("table/dt=2024-01-02/hour=00/", set.latest().unwrap().prefix);
+}
+
+#[test]
+fn finds_missing_hours_and_days() {
+    let objects = vec![
+        object("t/dt=2024-01-01/hour=00/a"),
+        object("t/dt=2024-01-01/hour=02/a"),
+        object("t/dt=2024-01-03/hour=00/a"),
+    ];
+    let set = PartitionSet::discover("t/", &objects);
+    let at = |d, h| NaiveDate::from_ymd_opt(2024, 1, d).unwrap().and_hms_opt(h, 0, 0).unwrap();
+
+    assert_eq!(vec![at(1, 1)], set.gaps("dt", Some("hour"), at(1, 0), at(1, 2)));
+    assert_eq!(vec![at(2, 0)], set.gaps("dt", None, at(1, 0), at(3, 0)));
+}
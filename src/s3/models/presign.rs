@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac, NewMac};
+use serde_derive::Serialize;
+use serde_json::{json, Map, Value};
+use sha2::Sha256;
+
+pub const MAX_PRESIGN_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+pub const SIGNING_ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+#[derive(Clone, Debug)]
+pub struct PresignOptions {
+    pub expires_in: Duration,
+    pub content_type: Option<String>,
+    pub response_content_type: Option<String>,
+    pub response_content_disposition: Option<String>,
+    pub response_cache_control: Option<String>,
+}
+
+impl Default for PresignOptions {
+    fn default() -> PresignOptions {
+        PresignOptions {
+            expires_in: Duration::from_secs(60 * 60),
+            content_type: None,
+            response_content_type: None,
+            response_content_disposition: None,
+            response_cache_control: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContentTypeCondition {
+    Exact(String),
+    StartsWith(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct PostPolicyOptions {
+    pub expires_in: Duration,
+    pub content_type: Option<ContentTypeCondition>,
+    pub content_length_range: Option<(u64, u64)>,
+    pub success_action_status: Option<u16>,
+}
+
+impl Default for PostPolicyOptions {
+    fn default() -> PostPolicyOptions {
+        PostPolicyOptions {
+            expires_in: Duration::from_secs(60 * 60),
+            content_type: None,
+            content_length_range: None,
+            success_action_status: None,
+        }
+    }
+}
+
+/// The form a browser posts to `url`: every entry in `fields` goes in as a form
+/// field ahead of the `file` field.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PresignedPost {
+    pub url: String,
+    pub fields: BTreeMap<String, String>,
+}
+
+impl PresignedPost {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+pub fn validate_expiry(expires_in: Duration) -> Result<(), String> {
+    if expires_in.as_secs() == 0 || expires_in > MAX_PRESIGN_EXPIRY {
+        Err(format!("presigned expiry must be between 1 second and {} seconds", MAX_PRESIGN_EXPIRY.as_secs()))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn credential_scope(access_key_id: &str, date: &DateTime<Utc>, region: &str) -> String {
+    format!("{}/{}/{}/s3/aws4_request", access_key_id, date.format("%Y%m%d"), region)
+}
+
+/// Form fields and policy conditions for a browser upload to `key`. A key ending
+/// in `/` allows any key under it, with the browser's filename appended.
+pub fn post_policy(bucket: &str, key: &str, credential: &str, date: &DateTime<Utc>, security_token: Option<&str>, options: &PostPolicyOptions) -> (BTreeMap<String, String>, Value) {
+    let mut fields = BTreeMap::new();
+    let mut conditions = vec![json!({ "bucket": bucket })];
+
+    if key.is_empty() || key.ends_with('/') {
+        fields.insert("key".to_string(), format!("{}${{filename}}", key));
+        conditions.push(json!(["starts-with", "$key", key]));
+    } else {
+        fields.insert("key".to_string(), key.to_string());
+        conditions.push(json!({ "key": key }));
+    }
+
+    let mut signed = vec![
+        ("x-amz-algorithm", SIGNING_ALGORITHM.to_string()),
+        ("x-amz-credential", credential.to_string()),
+        ("x-amz-date", date.format("%Y%m%dT%H%M%SZ").to_string()),
+    ];
+    if let Some(token) = security_token { signed.push(("x-amz-security-token", token.to_string())); }
+    if let Some(status) = options.success_action_status { signed.push(("success_action_status", status.to_string())); }
+    for (name, value) in signed {
+        let mut condition = Map::new();
+        condition.insert(name.to_string(), Value::String(value.clone()));
+        conditions.push(Value::Object(condition));
+        fields.insert(name.to_string(), value);
+    }
+
+    match &options.content_type {
+        Some(ContentTypeCondition::Exact(content_type)) => {
+            conditions.push(json!({ "Content-Type": content_type }));
+            fields.insert("Content-Type".to_string(), content_type.clone());
+        },
+        Some(ContentTypeCondition::StartsWith(prefix)) => conditions.push(json!(["starts-with", "$Content-Type", prefix])),
+        None => {},
+    }
+    if let Some((min, max)) = options.content_length_range {
+        conditions.push(json!(["content-length-range", min, max]));
+    }
+
+    let expiration = *date + chrono::Duration::seconds(options.expires_in.as_secs() as i64);
+    let policy = json!({
+        "expiration": expiration.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+        "conditions": conditions,
+    });
+    (fields, policy)
+}
+
+pub fn signing_key(secret_access_key: &str, date: &DateTime<Utc>, region: &str, service: &str) -> Vec<u8> {
+    let date_key = hmac_sha256(format!("AWS4{}", secret_access_key).as_bytes(), date.format("%Y%m%d").to_string().as_bytes());
+    let region_key = hmac_sha256(&date_key, region.as_bytes());
+    let service_key = hmac_sha256(&region_key, service.as_bytes());
+    hmac_sha256(&service_key, b"aws4_request")
+}
+
+pub fn sign(signing_key: &[u8], string_to_sign: &str) -> String {
+    hmac_sha256(signing_key, string_to_sign.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+fn date(rfc3339: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+}
+
+#[test]
+fn signing_key_matches_aws_example() {
+    let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", &date("2012-02-15T00:00:00Z"), "us-east-1", "iam");
+    assert_eq!("f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d", key.iter().map(|b| format!("{:02x}", b)).collect::<String>());
+    assert_eq!("fd57eef02531ba451845d15612bcf65da542aea2390f5d3acf572ac3de547ee8", sign(&key, "policy"));
+}
+
+#[test]
+fn post_policy_for_prefix_allows_any_filename() {
+    let options = PostPolicyOptions {
+        content_type: Some(ContentTypeCondition::StartsWith("image/".to_string())),
+        content_length_range: Some((1, 1024)),
+        ..PostPolicyOptions::default()
+    };
+    let (fields, policy) = post_policy("bucket", "uploads/", "AKID/20200101/eu-west-1/s3/aws4_request", &date("2020-01-01T00:00:00Z"), None, &options);
+
+    assert_eq!("uploads/${filename}", fields["key"]);
+    assert_eq!("20200101T000000Z", fields["x-amz-date"]);
+    assert!(!fields.contains_key("Content-Type"));
+    assert_eq!("2020-01-01T01:00:00.000Z", policy["expiration"]);
+    let conditions = policy["conditions"].as_array().unwrap();
+    assert!(conditions.contains(&json!(["starts-with", "$key", "uploads/"])));
+    assert!(conditions.contains(&json!(["starts-with", "$Content-Type", "image/"])));
+    assert!(conditions.contains(&json!(["content-length-range", 1, 1024])));
+}
+
+#[test]
+fn expiry_is_bounded_to_seven_days() {
+    assert!(validate_expiry(Duration::from_secs(3600)).is_ok());
+    assert!(validate_expiry(Duration::from_secs(0)).is_err());
+    assert!(validate_expiry(MAX_PRESIGN_EXPIRY + Duration::from_secs(1)).is_err());
+}
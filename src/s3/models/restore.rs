@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use serde_derive::Serialize;
+use crate::s3::models::object_metadata::RestoreStatus;
+use crate::s3::models::operation::OperationFailure;
+
+pub const ARCHIVE_STORAGE_CLASSES: [&str; 2] = ["GLACIER", "DEEP_ARCHIVE"];
+
+pub fn is_archive_storage_class(storage_class: &str) -> bool {
+    ARCHIVE_STORAGE_CLASSES.contains(&storage_class)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RestoreTier {
+    Expedited,
+    Standard,
+    Bulk,
+}
+
+impl RestoreTier {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            RestoreTier::Expedited => "Expedited",
+            RestoreTier::Standard => "Standard",
+            RestoreTier::Bulk => "Bulk",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RestoreOptions {
+    pub days: i64,
+    pub tier: RestoreTier,
+    pub recursive: bool,
+    pub concurrency: usize,
+    pub requests_per_second: Option<u32>,
+}
+
+impl Default for RestoreOptions {
+    fn default() -> RestoreOptions {
+        RestoreOptions {
+            days: 7,
+            tier: RestoreTier::Standard,
+            recursive: false,
+            concurrency: 8,
+            requests_per_second: Some(50),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreRequestOutcome {
+    Initiated,
+    AlreadyInProgress,
+    NotArchived,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct RestoreRequestResult {
+    pub key: String,
+    pub outcome: RestoreRequestOutcome,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreState {
+    NotArchived,
+    NotRequested,
+    Pending,
+    Complete,
+}
+
+impl RestoreState {
+    pub fn from(storage_class: &str, restore: &Option<RestoreStatus>) -> RestoreState {
+        if !is_archive_storage_class(storage_class) { return RestoreState::NotArchived; }
+        match restore {
+            None => RestoreState::NotRequested,
+            Some(status) if status.ongoing => RestoreState::Pending,
+            Some(_) => RestoreState::Complete,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct RestoreObjectStatus {
+    pub key: String,
+    pub storage_class: String,
+    pub state: RestoreState,
+    pub expiry: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct RestoreStatusReport {
+    pub not_requested: usize,
+    pub pending: usize,
+    pub complete: usize,
+    pub objects: Vec<RestoreObjectStatus>,
+    /// Objects whose status couldn't be read.
+    pub failures: Vec<OperationFailure>,
+}
+
+impl RestoreStatusReport {
+    pub fn from(objects: Vec<RestoreObjectStatus>, failures: Vec<OperationFailure>) -> RestoreStatusReport {
+        let count = |state: RestoreState| objects.iter().filter(|o| o.state == state).count();
+        RestoreStatusReport {
+            not_requested: count(RestoreState::NotRequested),
+            pending: count(RestoreState::Pending),
+            complete: count(RestoreState::Complete),
+            objects,
+            failures,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.not_requested == 0 && self.pending == 0 && self.failures.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[test]
+fn restore_state_follows_storage_class_and_header() {
+    let ongoing = Some(RestoreStatus { ongoing: true, expiry: None });
+    let done = Some(RestoreStatus { ongoing: false, expiry: None });
+    assert_eq!(RestoreState::NotArchived, RestoreState::from("STANDARD", &ongoing));
+    assert_eq!(RestoreState::NotRequested, RestoreState::from("GLACIER", &None));
+    assert_eq!(RestoreState::Pending, RestoreState::from("DEEP_ARCHIVE", &ongoing));
+    assert_eq!(RestoreState::Complete, RestoreState::from("GLACIER", &done));
+}
+
+#[test]
+fn status_report_counts_states() {
+    let object = |state| RestoreObjectStatus { key: "k".to_string(), storage_class: "GLACIER".to_string(), state, expiry: None };
+    let report = RestoreStatusReport::from(vec![object(RestoreState::Pending), object(RestoreState::Complete), object(RestoreState::Complete)], vec![]);
+    assert_eq!(0, report.not_requested);
+    assert_eq!(1, report.pending);
+    assert_eq!(2, report.complete);
+    assert!(!report.is_complete());
+}
+
+#[test]
+fn status_report_with_failures_is_incomplete() {
+    let complete = RestoreObjectStatus { key: "a".to_string(), storage_class: "GLACIER".to_string(), state: RestoreState::Complete, expiry: None };
+    let failure = OperationFailure { bucket: "b".to_string(), key: "c".to_string(), code: None, message: "Access Denied".to_string() };
+    assert!(RestoreStatusReport::from(vec![complete.clone()], vec![]).is_complete());
+    assert!(!RestoreStatusReport::from(vec![complete], vec![failure]).is_complete());
+}
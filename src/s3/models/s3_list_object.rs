@@ -1,10 +1,16 @@
 use serde_derive::Serialize;
+use chrono::{DateTime, Utc};
+use crate::s3::models::etag;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone)]
 pub struct S3ListObject {
-    last_modified: String,
-    size: i64,
-    key: String,
+    pub last_modified: String,
+    pub size: i64,
+    pub key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_class: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e_tag: Option<String>,
 }
 
 impl S3ListObject {
@@ -12,7 +18,52 @@ impl S3ListObject {
         S3ListObject {
             last_modified: o.last_modified.as_ref().unwrap().to_string(),
             size: o.size.as_ref().unwrap().to_owned(),
-            key: o.key.as_ref().unwrap().to_string()
+            key: o.key.as_ref().unwrap().to_string(),
+            storage_class: o.storage_class.clone(),
+            e_tag: o.e_tag.as_ref().map(|t| etag::normalise(t)),
         }
     }
-}
\ No newline at end of file
+
+    pub fn last_modified_date_time(&self) -> Option<DateTime<Utc>> {
+        match DateTime::parse_from_rfc3339(&self.last_modified) {
+            Ok(date_time) => Some(date_time.with_timezone(&Utc)),
+            Err(_e) => None,
+        }
+    }
+}
+
+#[test]
+fn last_modified_is_parsed_from_listing_timestamp() {
+    let object = S3ListObject {
+        last_modified: "2020-09-01T10:11:12.000Z".to_string(),
+        size: 10,
+        key: "a/b.parquet".to_string(),
+        storage_class: Some("STANDARD".to_string()),
+        e_tag: None,
+    };
+    assert_eq!("2020-09-01T10:11:12+00:00", object.last_modified_date_time().unwrap().to_rfc3339());
+}
+
+#[test]
+fn unparseable_last_modified_is_none() {
+    let object = S3ListObject {
+        last_modified: "yesterday".to_string(),
+        size: 10,
+        key: "a/b.parquet".to_string(),
+        storage_class: None,
+        e_tag: None,
+    };
+    assert!(object.last_modified_date_time().is_none());
+}
+
+#[test]
+fn missing_storage_class_and_e_tag_are_not_serialized() {
+    let object = S3ListObject {
+        last_modified: "2020-09-01T10:11:12.000Z".to_string(),
+        size: 10,
+        key: "a/b.parquet".to_string(),
+        storage_class: None,
+        e_tag: None,
+    };
+    assert_eq!("{\"last_modified\":\"2020-09-01T10:11:12.000Z\",\"size\":10,\"key\":\"a/b.parquet\"}", serde_json::to_string(&object).unwrap());
+}
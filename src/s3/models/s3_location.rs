@@ -1,51 +1,494 @@
-use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::s3::models::s3_location_error::S3LocationError;
+use crate::s3::models::upload::url_encode;
 
 #[derive(Clone,Debug,PartialEq)]
 pub struct S3Location {
     pub bucket: String,
     pub key: String,
+    pub region: Option<String>,
+    pub scheme: String,
+    /// The AWS partition, `aws`, `aws-cn` or `aws-us-gov`, which decides the ARN
+    /// prefix and the domain of `https` URLs.
+    pub partition: String,
 }
 
+const S3_URI: &str = r"^(?P<scheme>s3[an]?)://(?P<bucket>[^/]+)(?:/(?P<key>.*))?$";
+const VIRTUAL_HOSTED: &str = r"^(?P<scheme>https?)://(?P<bucket>[^/]+?)\.s3(?:[.-]dualstack)?(?:[.-](?P<region>[a-z0-9-]+))?\.amazonaws\.com(?P<china>\.cn)?(?:/(?P<key>.*))?$";
+const PATH_STYLE: &str = r"^(?P<scheme>https?)://s3(?:[.-]dualstack)?(?:[.-](?P<region>[a-z0-9-]+))?\.amazonaws\.com(?P<china>\.cn)?/(?P<bucket>[^/]+)(?:/(?P<key>.*))?$";
+const ACCESS_POINT_ARN: &str = r"^arn:(?P<partition>aws[a-z-]*):s3:(?P<region>[a-z0-9-]+):(?P<account>\d{12}):accesspoint[/:](?P<name>[^/]+)(?:/object/(?P<key>.*))?$";
+const OBJECT_ARN: &str = r"^arn:(?P<partition>aws[a-z-]*):s3:::(?P<bucket>[^/]+)(?:/(?P<key>.*))?$";
+
+const DEFAULT_PARTITION: &str = "aws";
+
+const MAX_KEY_BYTES: usize = 1024;
+const RESERVED_BUCKET_PREFIXES: [&str; 3] = ["xn--", "sthree-", "amzn-s3-demo-"];
+const RESERVED_BUCKET_SUFFIXES: [&str; 5] = ["-s3alias", "--ol-s3", ".mrap", "--x-s3", "--table-s3"];
+
 impl S3Location {
-    pub fn from(path: &str) -> Result<S3Location, String> {
-        let re = Regex::new(r"s3[an]?://(?P<bucket>[^/]+)/(?P<key>.*)").unwrap();
-        let results = re.captures_iter(path)
-            .map(|cap| S3Location { bucket: cap[1].to_string(), key: cap[2].to_string() })
-            .collect::<Vec::<S3Location>>();
-        match results.len() {
-            1 => Ok(results[0].clone()),
-            0 => Err("No results!".to_string()),
-            _ => Err("Too many results!".to_string()),
+    pub fn new(bucket: &str, key: &str) -> S3Location {
+        S3Location { bucket: bucket.to_string(), key: key.to_string(), region: None, scheme: "s3".to_string(), partition: DEFAULT_PARTITION.to_string() }
+    }
+
+    /// Parses `s3://`, `s3a://` and `s3n://` URIs (with or without a key), virtual-hosted
+    /// and path-style `https://` URLs, access point ARNs and S3 object ARNs. For access
+    /// points the access point ARN stands in for the bucket, as the S3 API accepts it there.
+    pub fn from(path: &str) -> Result<S3Location, S3LocationError> {
+        if let Some(cap) = Regex::new(S3_URI).unwrap().captures(path) {
+            return Ok(S3Location {
+                bucket: cap["bucket"].to_string(),
+                key: group(&cap, "key"),
+                region: None,
+                scheme: cap["scheme"].to_string(),
+                partition: DEFAULT_PARTITION.to_string(),
+            });
+        }
+        for pattern in &[VIRTUAL_HOSTED, PATH_STYLE] {
+            if let Some(cap) = Regex::new(pattern).unwrap().captures(path) {
+                let region = cap.name("region").map(|r| r.as_str().to_string()).filter(|r| r != "external-1");
+                let partition = match (&region, cap.name("china")) {
+                    (_, Some(_)) => "aws-cn",
+                    (Some(region), None) => partition_for_region(region),
+                    (None, None) => DEFAULT_PARTITION,
+                };
+                return Ok(S3Location {
+                    bucket: cap["bucket"].to_string(),
+                    key: url_decode(&group(&cap, "key")),
+                    region,
+                    scheme: cap["scheme"].to_string(),
+                    partition: partition.to_string(),
+                });
+            }
+        }
+        if let Some(cap) = Regex::new(ACCESS_POINT_ARN).unwrap().captures(path) {
+            return Ok(S3Location {
+                bucket: format!("arn:{}:s3:{}:{}:accesspoint/{}", &cap["partition"], &cap["region"], &cap["account"], &cap["name"]),
+                key: group(&cap, "key"),
+                region: Some(cap["region"].to_string()),
+                scheme: "arn".to_string(),
+                partition: cap["partition"].to_string(),
+            });
+        }
+        if let Some(cap) = Regex::new(OBJECT_ARN).unwrap().captures(path) {
+            return Ok(S3Location {
+                bucket: cap["bucket"].to_string(),
+                key: group(&cap, "key"),
+                region: None,
+                scheme: "arn".to_string(),
+                partition: cap["partition"].to_string(),
+            });
+        }
+        Err(S3LocationError::Unrecognised(path.to_string()))
+    }
+
+    /// Parses URLs on a custom S3-compatible endpoint such as MinIO or LocalStack, in
+    /// path style (`http://localhost:9000/bucket/key`) or virtual-hosted style
+    /// (`http://bucket.localhost:9000/key`). The endpoint belongs to the client rather
+    /// than the location, so these come back as `s3://` locations. Anything not on the
+    /// endpoint is parsed as `from` does.
+    pub fn from_endpoint(path: &str, endpoint: &str) -> Result<S3Location, S3LocationError> {
+        let endpoint = endpoint.trim_end_matches('/');
+        if let Some((scheme, host)) = endpoint.split_once("://") {
+            let path_style = path.strip_prefix(endpoint).and_then(|rest| rest.strip_prefix('/'))
+                .map(|rest| rest.split_once('/').unwrap_or((rest, "")));
+            let virtual_hosted = path.strip_prefix(scheme).and_then(|rest| rest.strip_prefix("://"))
+                .map(|rest| rest.split_once('/').unwrap_or((rest, "")))
+                .and_then(|(authority, key)| authority.strip_suffix(host).and_then(|b| b.strip_suffix('.')).map(|bucket| (bucket, key)));
+            if let Some((bucket, key)) = path_style.or(virtual_hosted).filter(|(bucket, _)| !bucket.is_empty()) {
+                return Ok(S3Location::new(bucket, &url_decode(key)));
+            }
+        }
+        S3Location::from(path)
+    }
+
+    /// Parses as `from` does, then checks the bucket name against the AWS naming
+    /// rules and the key against S3's length and character limits.
+    pub fn from_strict(path: &str) -> Result<S3Location, S3LocationError> {
+        let location = S3Location::from(path)?;
+        location.validate()?;
+        Ok(location)
+    }
+
+    pub fn validate(&self) -> Result<(), S3LocationError> {
+        if !self.is_access_point() { validate_bucket_name(&self.bucket)?; }
+        validate_key(&self.key)
+    }
+
+    pub fn is_access_point(&self) -> bool {
+        self.bucket.starts_with("arn:")
+    }
+
+    /// A prefix is the bucket root or a key ending in `/`.
+    pub fn is_prefix(&self) -> bool {
+        self.key.is_empty() || self.key.ends_with('/')
+    }
+
+    /// Appends `segment` to the key, adding a `/` between them only when needed.
+    pub fn join(&self, segment: &str) -> S3Location {
+        let segment = segment.trim_start_matches('/');
+        let key = if self.is_prefix() { format!("{}{}", self.key, segment) } else { format!("{}/{}", self.key, segment) };
+        self.with_key(&key)
+    }
+
+    /// The enclosing prefix, always ending in `/` (or the bucket root). `None` at the bucket root.
+    pub fn parent(&self) -> Option<S3Location> {
+        let trimmed = self.key.trim_end_matches('/');
+        if trimmed.is_empty() { return None; }
+        match trimmed.rfind('/') {
+            Some(i) => Some(self.with_key(&trimmed[..=i])),
+            None => Some(self.with_key("")),
+        }
+    }
+
+    /// The last segment of the key, ignoring any trailing `/`.
+    pub fn file_name(&self) -> Option<&str> {
+        let trimmed = self.key.trim_end_matches('/');
+        match trimmed.rsplit('/').next() {
+            Some(name) if !name.is_empty() => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> Option<&str> {
+        match self.file_name().and_then(|name| name.rsplit_once('.')) {
+            Some((stem, extension)) if !stem.is_empty() => Some(extension),
+            _ => None,
         }
     }
+
+    /// Replaces the extension of the file name, or removes it when `extension` is empty.
+    pub fn with_extension(&self, extension: &str) -> S3Location {
+        let file_name = match self.file_name() {
+            Some(name) => name,
+            None => return self.clone(),
+        };
+        let stem = match self.extension() {
+            Some(current) => &file_name[..file_name.len() - current.len() - 1],
+            None => file_name,
+        };
+        let new_name = if extension.is_empty() { stem.to_string() } else { format!("{}.{}", stem, extension) };
+        let trimmed = self.key.trim_end_matches('/');
+        let key = format!("{}{}{}", &trimmed[..trimmed.len() - file_name.len()], new_name, &self.key[trimmed.len()..]);
+        self.with_key(&key)
+    }
+
+    /// True when both are in the same bucket and `base` is this key or one of its
+    /// ancestors. Matching is by whole path segments, so `a/b` does not start with `a/bc`.
+    pub fn starts_with(&self, base: &S3Location) -> bool {
+        if self.bucket != base.bucket { return false; }
+        base.key.is_empty() ||
+            self.key == base.key ||
+            (base.key.ends_with('/') && self.key.starts_with(&base.key)) ||
+            self.key.starts_with(&format!("{}/", base.key))
+    }
+
+    /// The key relative to `base`, without a leading `/`.
+    pub fn strip_prefix(&self, base: &S3Location) -> Option<String> {
+        if !self.starts_with(base) { return None; }
+        Some(self.key[base.key.len()..].trim_start_matches('/').to_string())
+    }
+
+    fn with_key(&self, key: &str) -> S3Location {
+        S3Location { key: key.to_string(), ..self.clone() }
+    }
+}
+
+/// Formats in the scheme the location was parsed from. `https` locations are always
+/// written virtual-hosted style on their partition's domain, ARNs as object or
+/// access point ARNs.
+impl fmt::Display for S3Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.scheme.as_str() {
+            "http" | "https" => {
+                let key = self.key.split('/').map(url_encode).collect::<Vec<String>>().join("/");
+                let domain = dns_suffix(&self.partition);
+                match &self.region {
+                    Some(region) => write!(f, "{}://{}.s3.{}.{}/{}", self.scheme, self.bucket, region, domain, key),
+                    None => write!(f, "{}://{}.s3.{}/{}", self.scheme, self.bucket, domain, key),
+                }
+            },
+            "arn" if self.is_access_point() => write!(f, "{}/object/{}", self.bucket, self.key),
+            "arn" => write!(f, "arn:{}:s3:::{}/{}", self.partition, self.bucket, self.key),
+            scheme => write!(f, "{}://{}/{}", scheme, self.bucket, self.key),
+        }
+    }
+}
+
+impl FromStr for S3Location {
+    type Err = S3LocationError;
+
+    fn from_str(path: &str) -> Result<S3Location, S3LocationError> {
+        S3Location::from(path)
+    }
+}
+
+impl Serialize for S3Location {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for S3Location {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<S3Location, D::Error> {
+        use serde::de::Error;
+        let path = String::deserialize(deserializer)?;
+        S3Location::from(&path).map_err(D::Error::custom)
+    }
+}
+
+pub fn validate_bucket_name(bucket: &str) -> Result<(), S3LocationError> {
+    let error = |make: fn(String) -> S3LocationError| Err(make(bucket.to_string()));
+    if bucket.len() < 3 || bucket.len() > 63 { return error(S3LocationError::BucketNameLength); }
+    if !bucket.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.' || c == '-') {
+        return error(S3LocationError::BucketNameCharacters);
+    }
+    let alphanumeric = |c: Option<char>| matches!(c, Some(c) if c.is_ascii_alphanumeric());
+    if !alphanumeric(bucket.chars().next()) || !alphanumeric(bucket.chars().last()) {
+        return error(S3LocationError::BucketNameStartOrEnd);
+    }
+    if bucket.contains("..") { return error(S3LocationError::BucketNameAdjacentPeriods); }
+    if Regex::new(r"^\d{1,3}(\.\d{1,3}){3}$").unwrap().is_match(bucket) { return error(S3LocationError::BucketNameIpAddress); }
+    if let Some(prefix) = RESERVED_BUCKET_PREFIXES.iter().find(|p| bucket.starts_with(*p)) {
+        return Err(S3LocationError::BucketNameReservedPrefix(bucket.to_string(), *prefix));
+    }
+    if let Some(suffix) = RESERVED_BUCKET_SUFFIXES.iter().find(|s| bucket.ends_with(*s)) {
+        return Err(S3LocationError::BucketNameReservedSuffix(bucket.to_string(), *suffix));
+    }
+    Ok(())
+}
+
+pub fn validate_key(key: &str) -> Result<(), S3LocationError> {
+    if key.len() > MAX_KEY_BYTES { return Err(S3LocationError::KeyTooLong(key.len())); }
+    if key.chars().any(|c| c.is_control() && c != '\t' && c != '\n' && c != '\r') {
+        return Err(S3LocationError::KeyControlCharacters(key.to_string()));
+    }
+    Ok(())
+}
+
+/// The partition a region belongs to.
+pub fn partition_for_region(region: &str) -> &'static str {
+    if region.starts_with("cn-") {
+        "aws-cn"
+    } else if region.starts_with("us-gov-") {
+        "aws-us-gov"
+    } else {
+        DEFAULT_PARTITION
+    }
+}
+
+/// The domain S3 endpoints in a partition are under.
+pub fn dns_suffix(partition: &str) -> &'static str {
+    if partition == "aws-cn" { "amazonaws.com.cn" } else { "amazonaws.com" }
+}
+
+fn group(cap: &Captures, name: &str) -> String {
+    cap.name(name).map_or("".to_string(), |m| m.as_str().to_string())
+}
+
+pub(crate) fn url_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = if bytes[i] == b'%' && i + 2 < bytes.len() {
+            std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|h| u8::from_str_radix(h, 16).ok())
+        } else { None };
+        match hex {
+            Some(b) => { decoded.push(b); i += 3; },
+            None => { decoded.push(bytes[i]); i += 1; },
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
 }
 
 #[test]
 fn test_valid_s3_locations() {
     let test_cases = vec![
-        ("this-is-the-bucket", "here-is/the-key", "s3://this-is-the-bucket/here-is/the-key"),
-        ("this-is-the-bucket", "here-is/the-key", "s3a://this-is-the-bucket/here-is/the-key"),
-        ("this-is-the-bucket", "here-is/the-key", "s3n://this-is-the-bucket/here-is/the-key"),
-        ("this_is_the_bucket", "here_is/the_key", "s3://this_is_the_bucket/here_is/the_key"),
-        ("this_is_the_bucket", "", "s3://this_is_the_bucket/"),
+        ("this-is-the-bucket", "here-is/the-key", "s3", "s3://this-is-the-bucket/here-is/the-key"),
+        ("this-is-the-bucket", "here-is/the-key", "s3a", "s3a://this-is-the-bucket/here-is/the-key"),
+        ("this-is-the-bucket", "here-is/the-key", "s3n", "s3n://this-is-the-bucket/here-is/the-key"),
+        ("this_is_the_bucket", "here_is/the_key", "s3", "s3://this_is_the_bucket/here_is/the_key"),
+        ("this_is_the_bucket", "", "s3", "s3://this_is_the_bucket/"),
+        ("this-is-the-bucket", "", "s3", "s3://this-is-the-bucket"),
     ];
-    test_cases.iter().for_each(|(b, k, p )| {
+    test_cases.iter().for_each(|(b, k, s, p )| {
         let actual= S3Location::from(p);
-        let expected = S3Location { bucket: b.to_string(), key: k.to_string() };
+        let expected = S3Location { bucket: b.to_string(), key: k.to_string(), region: None, scheme: s.to_string(), partition: "aws".to_string() };
         assert!(actual.is_ok());
         assert_eq!(expected, actual.unwrap());
     });
 }
 
+#[test]
+fn test_valid_https_locations() {
+    let test_cases = vec![
+        ("my.bucket", "a/b c.csv", Some("eu-west-1"), "https://my.bucket.s3.eu-west-1.amazonaws.com/a/b%20c.csv"),
+        ("my-bucket", "a/b.csv", Some("us-west-2"), "https://my-bucket.s3-us-west-2.amazonaws.com/a/b.csv"),
+        ("my-bucket", "a/b.csv", None, "https://my-bucket.s3.amazonaws.com/a/b.csv"),
+        ("my-bucket", "", None, "https://my-bucket.s3.amazonaws.com"),
+        ("my-bucket", "a/b.csv", Some("eu-west-1"), "https://s3.eu-west-1.amazonaws.com/my-bucket/a/b.csv"),
+        ("my-bucket", "a/", None, "https://s3.amazonaws.com/my-bucket/a/"),
+    ];
+    test_cases.iter().for_each(|(b, k, r, p)| {
+        let actual = S3Location::from(p).unwrap();
+        assert_eq!(S3Location { bucket: b.to_string(), key: k.to_string(), region: r.map(|r| r.to_string()), scheme: "https".to_string(), partition: "aws".to_string() }, actual);
+    });
+    let china = S3Location::from("https://s3.cn-north-1.amazonaws.com.cn/my-bucket/a").unwrap();
+    assert_eq!("my-bucket", china.bucket);
+    assert_eq!(Some("cn-north-1".to_string()), china.region);
+    assert_eq!("aws-cn", china.partition);
+    assert_eq!("aws-us-gov", S3Location::from("https://b.s3.us-gov-west-1.amazonaws.com/a").unwrap().partition);
+}
+
+#[test]
+fn test_valid_arn_locations() {
+    let access_point = S3Location::from("arn:aws:s3:us-west-2:123456789012:accesspoint/my-ap/object/a/b.csv").unwrap();
+    assert_eq!("arn:aws:s3:us-west-2:123456789012:accesspoint/my-ap", access_point.bucket);
+    assert_eq!("a/b.csv", access_point.key);
+    assert_eq!(Some("us-west-2".to_string()), access_point.region);
+    assert!(access_point.is_access_point());
+
+    let object = S3Location::from("arn:aws:s3:::my-bucket/a/b.csv").unwrap();
+    assert_eq!(S3Location { bucket: "my-bucket".to_string(), key: "a/b.csv".to_string(), region: None, scheme: "arn".to_string(), partition: "aws".to_string() }, object);
+    assert!(!object.is_access_point());
+}
+
 #[test]
 fn test_invalid_s3_locations() {
     let test_cases = vec![
         "s://this-is-the-bucket/",
-        "s3://this-is-the-bucket",
         "s3d://this-is-the-bucket/here-is/the-key",
+        "https://example.com/bucket/key",
+        "arn:aws:s3:us-west-2:123:accesspoint/ap",
+        "s3:///key",
     ];
     test_cases.iter().for_each(|p| {
         let actual= S3Location::from(p);
-        assert!(actual.is_err());
+        assert_eq!(Err(S3LocationError::Unrecognised(p.to_string())), actual);
     });
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_strict_validation_accepts_valid_names() {
+    vec![
+        "s3://this-is-the-bucket/here-is/the-key",
+        "s3://my.bucket.123/a",
+        "s3://abc/",
+        "arn:aws:s3:us-west-2:123456789012:accesspoint/My_AP/object/a",
+    ].iter().for_each(|p| assert!(S3Location::from_strict(p).is_ok(), "{}", p));
+}
+
+#[test]
+fn test_strict_validation_rejects_invalid_bucket_names() {
+    let test_cases = vec![
+        ("s3://this_is_the_bucket/here_is/the_key", S3LocationError::BucketNameCharacters("this_is_the_bucket".to_string())),
+        ("s3://Bucket/", S3LocationError::BucketNameCharacters("Bucket".to_string())),
+        ("s3://ab/", S3LocationError::BucketNameLength("ab".to_string())),
+        ("s3://-bucket/", S3LocationError::BucketNameStartOrEnd("-bucket".to_string())),
+        ("s3://bucket./", S3LocationError::BucketNameStartOrEnd("bucket.".to_string())),
+        ("s3://my..bucket/", S3LocationError::BucketNameAdjacentPeriods("my..bucket".to_string())),
+        ("s3://192.168.5.4/", S3LocationError::BucketNameIpAddress("192.168.5.4".to_string())),
+        ("s3://xn--bucket/", S3LocationError::BucketNameReservedPrefix("xn--bucket".to_string(), "xn--")),
+        ("s3://bucket-s3alias/", S3LocationError::BucketNameReservedSuffix("bucket-s3alias".to_string(), "-s3alias")),
+    ];
+    test_cases.into_iter().for_each(|(p, expected)| assert_eq!(Err(expected), S3Location::from_strict(p)));
+    assert!(S3Location::from("s3://this_is_the_bucket/here_is/the_key").is_ok());
+}
+
+#[test]
+fn test_strict_validation_rejects_invalid_keys() {
+    let long_key = format!("s3://bucket/{}", "é".repeat(513));
+    assert_eq!(Err(S3LocationError::KeyTooLong(1026)), S3Location::from_strict(&long_key));
+    assert_eq!(Err(S3LocationError::KeyControlCharacters("a\u{7}b".to_string())), S3Location::from_strict("s3://bucket/a\u{7}b"));
+}
+
+#[test]
+fn test_url_decoding_leaves_invalid_escapes() {
+    assert_eq!("a b+%zz%", url_decode("a%20b+%zz%"));
+}
+
+#[test]
+fn test_display_round_trips() {
+    vec![
+        "s3://bucket/a/b.csv",
+        "s3a://bucket/",
+        "https://bucket.s3.eu-west-1.amazonaws.com/a/b%20c.csv",
+        "arn:aws:s3:us-west-2:123456789012:accesspoint/my-ap/object/a/b.csv",
+        "arn:aws:s3:::bucket/a",
+        "https://bucket.s3.cn-north-1.amazonaws.com.cn/a",
+        "https://bucket.s3.us-gov-west-1.amazonaws.com/a",
+        "arn:aws-cn:s3:::bucket/a",
+        "arn:aws-us-gov:s3:::bucket/a",
+        "arn:aws-cn:s3:cn-north-1:123456789012:accesspoint/my-ap/object/a",
+    ].iter().for_each(|p| {
+        let location: S3Location = p.parse().unwrap();
+        assert_eq!(p.to_string(), location.to_string());
+    });
+    assert_eq!("s3://bucket/", S3Location::from("s3://bucket").unwrap().to_string());
+}
+
+#[test]
+fn test_serde_uses_the_string_form() {
+    let location = S3Location::from("s3://bucket/a/b.csv").unwrap();
+    let json = serde_json::to_string(&location).unwrap();
+    assert_eq!("\"s3://bucket/a/b.csv\"", json);
+    assert_eq!(location, serde_json::from_str::<S3Location>(&json).unwrap());
+    assert!(serde_json::from_str::<S3Location>("\"not a location\"").is_err());
+}
+
+#[test]
+fn test_join_handles_trailing_slashes() {
+    let root = S3Location::from("s3://bucket").unwrap();
+    assert_eq!("a", root.join("a").key);
+    assert_eq!("a/b", root.join("a").join("b").key);
+    assert_eq!("a/b", S3Location::from("s3://bucket/a/").unwrap().join("/b").key);
+}
+
+#[test]
+fn test_parent_and_file_name() {
+    let location = S3Location::from("s3://bucket/a/b/c.tar.gz").unwrap();
+    assert_eq!("a/b/", location.parent().unwrap().key);
+    assert_eq!("a/", location.parent().unwrap().parent().unwrap().key);
+    assert_eq!("", S3Location::from("s3://bucket/a").unwrap().parent().unwrap().key);
+    assert!(S3Location::from("s3://bucket/").unwrap().parent().is_none());
+    assert_eq!(Some("c.tar.gz"), location.file_name());
+    assert_eq!(Some("b"), S3Location::from("s3://bucket/a/b/").unwrap().file_name());
+    assert_eq!(None, S3Location::from("s3://bucket/").unwrap().file_name());
+}
+
+#[test]
+fn test_extensions() {
+    let location = S3Location::from("s3://bucket/a/c.tar.gz").unwrap();
+    assert_eq!(Some("gz"), location.extension());
+    assert_eq!("a/c.tar.zst", location.with_extension("zst").key);
+    assert_eq!("a/c.tar", location.with_extension("").key);
+    assert_eq!("a/c.csv", S3Location::from("s3://bucket/a/c").unwrap().with_extension("csv").key);
+    assert_eq!(None, S3Location::from("s3://bucket/.hidden").unwrap().extension());
+}
+
+#[test]
+fn test_prefix_relationships() {
+    let base = S3Location::from("s3://bucket/a/b").unwrap();
+    let child = S3Location::from("s3://bucket/a/b/c.csv").unwrap();
+    let sibling = S3Location::from("s3://bucket/a/bc/d.csv").unwrap();
+    assert!(child.starts_with(&base));
+    assert!(!sibling.starts_with(&base));
+    assert!(!child.starts_with(&S3Location::from("s3://other/a/b").unwrap()));
+    assert_eq!(Some("c.csv".to_string()), child.strip_prefix(&base));
+    assert_eq!(None, sibling.strip_prefix(&base));
+    assert!(S3Location::from("s3://bucket/a/").unwrap().is_prefix());
+    assert!(!child.is_prefix());
+}
+
+#[test]
+fn custom_endpoint_urls_are_parsed() {
+    let endpoint = "http://localhost:9000/";
+    assert_eq!(S3Location::new("bucket", "dir/a file.csv"), S3Location::from_endpoint("http://localhost:9000/bucket/dir/a%20file.csv", endpoint).unwrap());
+    assert_eq!(S3Location::new("bucket", ""), S3Location::from_endpoint("http://localhost:9000/bucket", endpoint).unwrap());
+    assert_eq!(S3Location::new("bucket", "key"), S3Location::from_endpoint("http://bucket.localhost:9000/key", endpoint).unwrap());
+    assert_eq!(S3Location::new("bucket", "key"), S3Location::from_endpoint("s3://bucket/key", endpoint).unwrap());
+    assert!(S3Location::from_endpoint("http://localhost:9000/", endpoint).is_err());
+}
@@ -0,0 +1,40 @@
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum S3LocationError {
+    Unrecognised(String),
+    BucketNameLength(String),
+    BucketNameCharacters(String),
+    BucketNameStartOrEnd(String),
+    BucketNameAdjacentPeriods(String),
+    BucketNameIpAddress(String),
+    BucketNameReservedPrefix(String, &'static str),
+    BucketNameReservedSuffix(String, &'static str),
+    KeyTooLong(usize),
+    KeyControlCharacters(String),
+}
+
+impl fmt::Display for S3LocationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            S3LocationError::Unrecognised(path) => write!(f, "'{}' is not a recognised S3 location", path),
+            S3LocationError::BucketNameLength(bucket) => write!(f, "Bucket name '{}' must be between 3 and 63 characters long", bucket),
+            S3LocationError::BucketNameCharacters(bucket) => write!(f, "Bucket name '{}' may only contain lowercase letters, numbers, dots and hyphens", bucket),
+            S3LocationError::BucketNameStartOrEnd(bucket) => write!(f, "Bucket name '{}' must begin and end with a letter or number", bucket),
+            S3LocationError::BucketNameAdjacentPeriods(bucket) => write!(f, "Bucket name '{}' must not contain two adjacent periods", bucket),
+            S3LocationError::BucketNameIpAddress(bucket) => write!(f, "Bucket name '{}' must not be formatted as an IP address", bucket),
+            S3LocationError::BucketNameReservedPrefix(bucket, prefix) => write!(f, "Bucket name '{}' must not start with the reserved prefix '{}'", bucket, prefix),
+            S3LocationError::BucketNameReservedSuffix(bucket, suffix) => write!(f, "Bucket name '{}' must not end with the reserved suffix '{}'", bucket, suffix),
+            S3LocationError::KeyTooLong(length) => write!(f, "Key is {} bytes long, the maximum is 1024 bytes of UTF-8", length),
+            S3LocationError::KeyControlCharacters(key) => write!(f, "Key '{}' contains control characters", key.escape_default()),
+        }
+    }
+}
+
+impl std::error::Error for S3LocationError {}
+
+#[test]
+fn errors_describe_the_problem() {
+    assert_eq!("'s3:/bucket' is not a recognised S3 location", S3LocationError::Unrecognised("s3:/bucket".to_string()).to_string());
+    assert_eq!("Bucket name 'xn--a' must not start with the reserved prefix 'xn--'", S3LocationError::BucketNameReservedPrefix("xn--a".to_string(), "xn--").to_string());
+}
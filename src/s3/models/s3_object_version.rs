@@ -0,0 +1,56 @@
+use serde_derive::Serialize;
+use chrono::{DateTime, Utc};
+use crate::s3::models::etag;
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct S3ObjectVersion {
+    pub key: String,
+    pub version_id: String,
+    pub last_modified: String,
+    pub is_latest: bool,
+    pub is_delete_marker: bool,
+    pub size: i64,
+    pub e_tag: Option<String>,
+    pub storage_class: Option<String>,
+}
+
+impl S3ObjectVersion {
+    pub fn from_version(v: &rusoto_s3::ObjectVersion) -> S3ObjectVersion {
+        S3ObjectVersion {
+            key: v.key.clone().unwrap_or_default(),
+            version_id: v.version_id.clone().unwrap_or_else(|| "null".to_string()),
+            last_modified: v.last_modified.clone().unwrap_or_default(),
+            is_latest: v.is_latest.unwrap_or(false),
+            is_delete_marker: false,
+            size: v.size.unwrap_or(0),
+            e_tag: v.e_tag.as_ref().map(|t| etag::normalise(t)),
+            storage_class: v.storage_class.clone(),
+        }
+    }
+
+    pub fn from_delete_marker(m: &rusoto_s3::DeleteMarkerEntry) -> S3ObjectVersion {
+        S3ObjectVersion {
+            key: m.key.clone().unwrap_or_default(),
+            version_id: m.version_id.clone().unwrap_or_else(|| "null".to_string()),
+            last_modified: m.last_modified.clone().unwrap_or_default(),
+            is_latest: m.is_latest.unwrap_or(false),
+            is_delete_marker: true,
+            size: 0,
+            e_tag: None,
+            storage_class: None,
+        }
+    }
+
+    pub fn last_modified_date_time(&self) -> Option<DateTime<Utc>> {
+        match DateTime::parse_from_rfc3339(&self.last_modified) {
+            Ok(date_time) => Some(date_time.with_timezone(&Utc)),
+            Err(_e) => None,
+        }
+    }
+
+    /// Orders versions of a key by when they were written. Versions written in the
+    /// same instant are told apart by which one S3 marks as latest.
+    pub fn recency(&self) -> (Option<DateTime<Utc>>, bool) {
+        (self.last_modified_date_time(), self.is_latest)
+    }
+}
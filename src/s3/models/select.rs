@@ -0,0 +1,208 @@
+use rusoto_s3::{CSVInput, CSVOutput, InputSerialization, JSONInput, JSONOutput, OutputSerialization, ParquetInput};
+use serde_derive::Serialize;
+
+pub const RECORD_DELIMITER: char = '\n';
+pub const QUOTE_CHARACTER: char = '"';
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CsvHeader {
+    Use,
+    Ignore,
+    None,
+}
+
+impl CsvHeader {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            CsvHeader::Use => "USE",
+            CsvHeader::Ignore => "IGNORE",
+            CsvHeader::None => "NONE",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Bzip2,
+}
+
+impl Compression {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Compression::None => "NONE",
+            Compression::Gzip => "GZIP",
+            Compression::Bzip2 => "BZIP2",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputFormat {
+    Csv { header: CsvHeader, delimiter: char, compression: Compression },
+    JsonLines { compression: Compression },
+    JsonDocument { compression: Compression },
+    Parquet,
+}
+
+impl InputFormat {
+    pub fn csv() -> InputFormat {
+        InputFormat::Csv { header: CsvHeader::Use, delimiter: ',', compression: Compression::None }
+    }
+
+    pub fn json_lines() -> InputFormat {
+        InputFormat::JsonLines { compression: Compression::None }
+    }
+
+    pub fn serialization(&self) -> InputSerialization {
+        match self {
+            InputFormat::Csv { header, delimiter, compression } => InputSerialization {
+                csv: Some(CSVInput {
+                    file_header_info: Some(header.as_str().to_string()),
+                    field_delimiter: Some(delimiter.to_string()),
+                    record_delimiter: Some(RECORD_DELIMITER.to_string()),
+                    ..CSVInput::default()
+                }),
+                compression_type: Some(compression.as_str().to_string()),
+                ..InputSerialization::default()
+            },
+            InputFormat::JsonLines { compression } => json_input("LINES", compression),
+            InputFormat::JsonDocument { compression } => json_input("DOCUMENT", compression),
+            InputFormat::Parquet => InputSerialization {
+                parquet: Some(ParquetInput {}),
+                ..InputSerialization::default()
+            },
+        }
+    }
+}
+
+fn json_input(type_: &str, compression: &Compression) -> InputSerialization {
+    InputSerialization {
+        json: Some(JSONInput { type_: Some(type_.to_string()) }),
+        compression_type: Some(compression.as_str().to_string()),
+        ..InputSerialization::default()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutputFormat {
+    Csv { delimiter: char },
+    Json,
+}
+
+impl OutputFormat {
+    pub fn serialization(&self) -> OutputSerialization {
+        match self {
+            OutputFormat::Csv { delimiter } => OutputSerialization {
+                csv: Some(CSVOutput {
+                    field_delimiter: Some(delimiter.to_string()),
+                    record_delimiter: Some(RECORD_DELIMITER.to_string()),
+                    quote_character: Some(QUOTE_CHARACTER.to_string()),
+                    ..CSVOutput::default()
+                }),
+                ..OutputSerialization::default()
+            },
+            OutputFormat::Json => OutputSerialization {
+                json: Some(JSONOutput { record_delimiter: Some(RECORD_DELIMITER.to_string()) }),
+                ..OutputSerialization::default()
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Default)]
+pub struct SelectStats {
+    pub bytes_scanned: i64,
+    pub bytes_processed: i64,
+    pub bytes_returned: i64,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum SelectEvent {
+    Record { record: String },
+    Progress { stats: SelectStats },
+    Stats { stats: SelectStats },
+    End,
+}
+
+/// Reassembles delimited records from payload chunks, which S3 splits at
+/// arbitrary byte offsets. For CSV output, newlines inside quoted fields are
+/// part of the record; JSON output escapes its newlines, so every one ends a record.
+#[derive(Debug, Default)]
+pub struct RecordBuffer {
+    pending: Vec<u8>,
+    quote: Option<u8>,
+    scanned: usize,
+    quoted: bool,
+}
+
+impl RecordBuffer {
+    pub fn new() -> RecordBuffer {
+        RecordBuffer::default()
+    }
+
+    pub fn for_output(format: &OutputFormat) -> RecordBuffer {
+        match format {
+            OutputFormat::Csv { .. } => RecordBuffer { quote: Some(QUOTE_CHARACTER as u8), ..RecordBuffer::default() },
+            OutputFormat::Json => RecordBuffer::new(),
+        }
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+        let mut records = vec![];
+        let mut start = 0;
+        for (i, b) in self.pending.iter().enumerate().skip(self.scanned) {
+            if Some(*b) == self.quote {
+                self.quoted = !self.quoted;
+            } else if *b == RECORD_DELIMITER as u8 && !self.quoted {
+                records.push(String::from_utf8_lossy(&self.pending[start..i]).to_string());
+                start = i + 1;
+            }
+        }
+        self.pending.drain(..start);
+        self.scanned = self.pending.len();
+        records
+    }
+
+    pub fn finish(&mut self) -> Option<String> {
+        if self.pending.is_empty() { return None; }
+        let record = String::from_utf8_lossy(&self.pending).to_string();
+        self.pending.clear();
+        self.scanned = 0;
+        self.quoted = false;
+        Some(record)
+    }
+}
+
+#[test]
+fn records_are_reassembled_across_chunks() {
+    let mut buffer = RecordBuffer::new();
+    assert_eq!(vec!["a,1".to_string()], buffer.push(b"a,1\nb,"));
+    assert_eq!(Vec::<String>::new(), buffer.push(b"2"));
+    assert_eq!(vec!["b,2".to_string(), "c,3".to_string()], buffer.push(b"\nc,3\nd"));
+    assert_eq!(Some("d".to_string()), buffer.finish());
+    assert_eq!(None, buffer.finish());
+}
+
+#[test]
+fn quoted_csv_newlines_stay_in_the_record() {
+    let mut buffer = RecordBuffer::for_output(&OutputFormat::Csv { delimiter: ',' });
+    assert_eq!(vec!["a,1".to_string()], buffer.push(b"a,1\nb,\"line one\n"));
+    assert_eq!(vec!["b,\"line one\nline \"\"two\"\"\"".to_string()], buffer.push(b"line \"\"two\"\"\"\nc,"));
+    assert_eq!(Some("c,".to_string()), buffer.finish());
+
+    let mut json = RecordBuffer::for_output(&OutputFormat::Json);
+    assert_eq!(vec!["{\"a\":\"\\\"\"}".to_string(), "{}".to_string()], json.push(b"{\"a\":\"\\\"\"}\n{}\n"));
+}
+
+#[test]
+fn csv_input_uses_header_and_delimiter() {
+    let serialization = InputFormat::Csv { header: CsvHeader::Ignore, delimiter: '|', compression: Compression::Gzip }.serialization();
+    let csv = serialization.csv.unwrap();
+    assert_eq!(Some("IGNORE".to_string()), csv.file_header_info);
+    assert_eq!(Some("|".to_string()), csv.field_delimiter);
+    assert_eq!(Some("GZIP".to_string()), serialization.compression_type);
+}
@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde_derive::Serialize;
+use crate::s3::models::download::DownloadOptions;
+use crate::s3::models::object_filter::glob_to_regex;
+use crate::s3::models::s3_location::S3Location;
+use crate::s3::models::upload::UploadOptions;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SyncEndpoint {
+    Local(PathBuf),
+    S3(S3Location),
+}
+
+impl SyncEndpoint {
+    /// `s3://`, `s3a://` and `s3n://` paths are S3 locations, anything else is a local path.
+    pub fn from(path: &str) -> Result<SyncEndpoint, String> {
+        if path.starts_with("s3://") || path.starts_with("s3a://") || path.starts_with("s3n://") {
+            S3Location::from(path).map(SyncEndpoint::S3).map_err(|e| e.to_string())
+        } else {
+            Ok(SyncEndpoint::Local(PathBuf::from(path)))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    SizeOnly,
+    SizeAndModified,
+    Checksum,
+}
+
+#[derive(Clone, Debug)]
+pub struct SyncOptions {
+    pub comparison: Comparison,
+    pub delete: bool,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub dry_run: bool,
+    pub concurrency: usize,
+    pub upload: UploadOptions,
+    pub download: DownloadOptions,
+}
+
+impl Default for SyncOptions {
+    fn default() -> SyncOptions {
+        SyncOptions {
+            comparison: Comparison::SizeAndModified,
+            delete: false,
+            include: vec![],
+            exclude: vec![],
+            dry_run: false,
+            concurrency: 8,
+            upload: UploadOptions::default(),
+            download: DownloadOptions::default(),
+        }
+    }
+}
+
+/// One file or object on either side of a sync, keyed by its path relative to the sync root.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyncEntry {
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    pub e_tag: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncReason {
+    Missing,
+    SizeDiffers,
+    Newer,
+    ChecksumDiffers,
+    Extraneous,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncChange {
+    pub key: String,
+    pub reason: SyncReason,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SyncAction {
+    Upload { path: String, bucket: String, key: String, size: u64, reason: SyncReason },
+    Download { bucket: String, key: String, path: String, size: u64, reason: SyncReason },
+    Copy { source_bucket: String, source_key: String, destination_bucket: String, destination_key: String, size: u64, reason: SyncReason },
+    DeleteLocal { path: String },
+    DeleteRemote { bucket: String, key: String },
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SyncFailure {
+    pub action: SyncAction,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct SyncReport {
+    pub dry_run: bool,
+    pub actions: Vec<SyncAction>,
+    pub completed: usize,
+    pub failures: Vec<SyncFailure>,
+}
+
+impl SyncReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// The default filter allows every key.
+#[derive(Clone, Default)]
+pub struct KeyFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl KeyFilter {
+    pub fn from(options: &SyncOptions) -> Result<KeyFilter, String> {
+        fn compile(globs: &[String]) -> Result<Vec<Regex>, String> {
+            globs.iter().map(|g| Regex::new(&glob_to_regex(g)).map_err(|e| format!("Invalid glob '{}': {}", g, e))).collect()
+        }
+        Ok(KeyFilter { include: compile(&options.include)?, exclude: compile(&options.exclude)? })
+    }
+
+    pub fn allows(&self, key: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|re| re.is_match(key))) &&
+            !self.exclude.iter().any(|re| re.is_match(key))
+    }
+}
+
+/// Works out which keys have to be transferred from `source` to `destination`
+/// and, with `delete`, which destination keys have no source any more.
+pub fn compare(source: &BTreeMap<String, SyncEntry>, destination: &BTreeMap<String, SyncEntry>, comparison: Comparison, delete: bool) -> Vec<SyncChange> {
+    let mut changes = source.iter().flat_map(|(key, s)| {
+        let reason = match destination.get(key) {
+            None => Some(SyncReason::Missing),
+            Some(d) if s.size != d.size => Some(SyncReason::SizeDiffers),
+            Some(d) => match comparison {
+                Comparison::SizeOnly => None,
+                Comparison::SizeAndModified => match (s.modified, d.modified) {
+                    (Some(sm), Some(dm)) if sm > dm => Some(SyncReason::Newer),
+                    _ => None,
+                },
+                Comparison::Checksum => match (&s.e_tag, &d.e_tag) {
+                    (Some(se), Some(de)) if se != de => Some(SyncReason::ChecksumDiffers),
+                    _ => None,
+                },
+            },
+        };
+        reason.map(|reason| SyncChange { key: key.clone(), reason })
+    }).collect::<Vec<SyncChange>>();
+
+    if delete {
+        destination.keys().filter(|key| !source.contains_key(*key)).for_each(|key| {
+            changes.push(SyncChange { key: key.clone(), reason: SyncReason::Extraneous });
+        });
+    }
+    changes
+}
+
+#[cfg(test)]
+fn entry(size: u64, modified: &str, e_tag: &str) -> SyncEntry {
+    SyncEntry {
+        size,
+        modified: Some(DateTime::parse_from_rfc3339(modified).unwrap().with_timezone(&Utc)),
+        e_tag: Some(e_tag.to_string()),
+    }
+}
+
+#[test]
+fn endpoints_are_parsed_from_paths() {
+    assert_eq!(SyncEndpoint::Local(PathBuf::from("/tmp/out")), SyncEndpoint::from("/tmp/out").unwrap());
+    match SyncEndpoint::from("s3://bucket/prefix/").unwrap() {
+        SyncEndpoint::S3(location) => assert_eq!("prefix/", location.key),
+        _ => panic!("expected an S3 endpoint"),
+    }
+}
+
+#[test]
+fn compare_finds_missing_changed_and_extraneous_keys() {
+    let mut source = BTreeMap::new();
+    source.insert("same".to_string(), entry(1, "2020-01-01T00:00:00Z", "a"));
+    source.insert("new".to_string(), entry(1, "2020-01-01T00:00:00Z", "a"));
+    source.insert("resized".to_string(), entry(2, "2020-01-01T00:00:00Z", "a"));
+    source.insert("touched".to_string(), entry(1, "2020-01-02T00:00:00Z", "a"));
+    let mut destination = BTreeMap::new();
+    destination.insert("same".to_string(), entry(1, "2020-01-01T00:00:00Z", "a"));
+    destination.insert("resized".to_string(), entry(1, "2020-01-01T00:00:00Z", "a"));
+    destination.insert("touched".to_string(), entry(1, "2020-01-01T00:00:00Z", "a"));
+    destination.insert("stale".to_string(), entry(1, "2020-01-01T00:00:00Z", "a"));
+
+    let changes = compare(&source, &destination, Comparison::SizeAndModified, true);
+    assert_eq!(vec![
+        SyncChange { key: "new".to_string(), reason: SyncReason::Missing },
+        SyncChange { key: "resized".to_string(), reason: SyncReason::SizeDiffers },
+        SyncChange { key: "touched".to_string(), reason: SyncReason::Newer },
+        SyncChange { key: "stale".to_string(), reason: SyncReason::Extraneous },
+    ], changes);
+
+    assert_eq!(3, compare(&source, &destination, Comparison::SizeAndModified, false).len());
+    assert_eq!(2, compare(&source, &destination, Comparison::SizeOnly, false).len());
+}
+
+#[test]
+fn checksum_comparison_uses_etags() {
+    let mut source = BTreeMap::new();
+    source.insert("k".to_string(), entry(1, "2020-01-02T00:00:00Z", "a"));
+    let mut destination = BTreeMap::new();
+    destination.insert("k".to_string(), entry(1, "2020-01-01T00:00:00Z", "a"));
+    assert!(compare(&source, &destination, Comparison::Checksum, false).is_empty());
+
+    destination.insert("k".to_string(), entry(1, "2020-01-01T00:00:00Z", "b"));
+    assert_eq!(SyncReason::ChecksumDiffers, compare(&source, &destination, Comparison::Checksum, false)[0].reason);
+}
+
+#[test]
+fn key_filter_applies_include_then_exclude() {
+    let options = SyncOptions { include: vec!["**/*.csv".to_string()], exclude: vec!["tmp/**".to_string()], ..SyncOptions::default() };
+    let filter = KeyFilter::from(&options).unwrap();
+    assert!(filter.allows("a/b.csv"));
+    assert!(!filter.allows("a/b.json"));
+    assert!(!filter.allows("tmp/b.csv"));
+}
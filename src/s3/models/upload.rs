@@ -0,0 +1,163 @@
+use std::collections::{BTreeMap, HashMap};
+use serde_derive::Serialize;
+
+pub const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+pub const MAX_PARTS: u64 = 10_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ServerSideEncryption {
+    S3,
+    Kms { key_id: Option<String> },
+}
+
+impl ServerSideEncryption {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ServerSideEncryption::S3 => "AES256",
+            ServerSideEncryption::Kms { .. } => "aws:kms",
+        }
+    }
+
+    pub fn kms_key_id(&self) -> Option<String> {
+        match self {
+            ServerSideEncryption::Kms { key_id } => key_id.clone(),
+            ServerSideEncryption::S3 => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct UploadOptions {
+    pub multipart_threshold: u64,
+    pub part_size: u64,
+    pub concurrency: usize,
+    pub content_type: Option<String>,
+    pub storage_class: Option<String>,
+    pub server_side_encryption: Option<ServerSideEncryption>,
+    pub metadata: BTreeMap<String, String>,
+    pub tags: BTreeMap<String, String>,
+}
+
+impl Default for UploadOptions {
+    fn default() -> UploadOptions {
+        UploadOptions {
+            multipart_threshold: 64 * 1024 * 1024,
+            part_size: 8 * 1024 * 1024,
+            concurrency: 4,
+            content_type: None,
+            storage_class: None,
+            server_side_encryption: None,
+            metadata: BTreeMap::new(),
+            tags: BTreeMap::new(),
+        }
+    }
+}
+
+impl UploadOptions {
+    /// Part size to use for an upload of `size` bytes, respecting S3's 5 MiB
+    /// minimum and 10,000 part maximum.
+    pub fn part_size_for(&self, size: Option<u64>) -> u64 {
+        let part_size = self.part_size.max(MIN_PART_SIZE);
+        match size {
+            Some(size) => part_size.max(size.div_ceil(MAX_PARTS)),
+            None => part_size,
+        }
+    }
+
+    /// The `x-amz-tagging` header value, `None` when there are no tags.
+    pub fn tagging(&self) -> Option<String> {
+        if self.tags.is_empty() { return None; }
+        Some(self.tags.iter()
+            .map(|(k, v)| format!("{}={}", url_encode(k), url_encode(v)))
+            .collect::<Vec<String>>()
+            .join("&"))
+    }
+
+    pub fn metadata(&self) -> Option<HashMap<String, String>> {
+        if self.metadata.is_empty() { None } else { Some(self.metadata.clone().into_iter().collect()) }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct UploadReport {
+    pub bucket: String,
+    pub key: String,
+    pub bytes: u64,
+    pub parts: usize,
+    pub e_tag: Option<String>,
+    pub version_id: Option<String>,
+}
+
+pub fn url_encode(value: &str) -> String {
+    value.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+        _ => format!("%{:02X}", b),
+    }).collect()
+}
+
+/// Guesses a MIME type from the extension of a file name or key.
+pub fn content_type_for(name: &str) -> &'static str {
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+    let extension = match file_name.rsplit_once('.') {
+        Some((_, extension)) => extension.to_lowercase(),
+        None => "".to_string(),
+    };
+    match extension.as_str() {
+        "avro" => "avro/binary",
+        "bz2" => "application/x-bzip2",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "gif" => "image/gif",
+        "gz" => "application/gzip",
+        "htm" | "html" => "text/html",
+        "jpeg" | "jpg" => "image/jpeg",
+        "js" => "application/javascript",
+        "json" => "application/json",
+        "jsonl" | "ndjson" => "application/x-ndjson",
+        "md" => "text/markdown",
+        "orc" => "application/x-orc",
+        "parquet" => "application/vnd.apache.parquet",
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "svg" => "image/svg+xml",
+        "tar" => "application/x-tar",
+        "tsv" => "text/tab-separated-values",
+        "txt" | "log" => "text/plain",
+        "xml" => "application/xml",
+        "yaml" | "yml" => "application/x-yaml",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+#[test]
+fn content_type_is_detected_from_extension() {
+    assert_eq!("text/csv", content_type_for("data/part-0.CSV"));
+    assert_eq!("application/json", content_type_for("manifest.json"));
+    assert_eq!("application/octet-stream", content_type_for("dir.v2/_SUCCESS"));
+}
+
+#[test]
+fn tags_are_url_encoded() {
+    let mut options = UploadOptions::default();
+    assert_eq!(None, options.tagging());
+    options.tags.insert("team".to_string(), "data eng".to_string());
+    options.tags.insert("cost-centre".to_string(), "a&b=c".to_string());
+    assert_eq!(Some("cost-centre=a%26b%3Dc&team=data%20eng".to_string()), options.tagging());
+}
+
+#[test]
+fn part_size_respects_s3_limits() {
+    let options = UploadOptions { part_size: 1024, ..UploadOptions::default() };
+    assert_eq!(MIN_PART_SIZE, options.part_size_for(None));
+    assert_eq!(MIN_PART_SIZE, options.part_size_for(Some(MIN_PART_SIZE * 10)));
+    assert_eq!(MIN_PART_SIZE * 2, options.part_size_for(Some(MIN_PART_SIZE * 2 * MAX_PARTS)));
+}
+
+#[test]
+fn server_side_encryption_maps_to_headers() {
+    assert_eq!("AES256", ServerSideEncryption::S3.as_str());
+    let kms = ServerSideEncryption::Kms { key_id: Some("alias/data".to_string()) };
+    assert_eq!("aws:kms", kms.as_str());
+    assert_eq!(Some("alias/data".to_string()), kms.kms_key_id());
+}
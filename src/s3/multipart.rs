@@ -0,0 +1,145 @@
+use std::error::Error;
+use chrono::{Duration, Utc};
+use futures::stream::{self, StreamExt};
+use rusoto_core::RusotoError;
+use rusoto_s3::{AbortMultipartUploadRequest, ListMultipartUploadsRequest, ListPartsRequest, Part, S3, S3Client};
+use tokio::runtime::Runtime;
+use crate::errors::models::error_response::ErrorResponse;
+use crate::s3::models::multipart_upload::{AbortOptions, AbortReport, S3MultipartUpload};
+use crate::s3::models::operation::OperationFailure;
+use crate::s3::models::s3_location::S3Location;
+
+const LIST_PARTS_CONCURRENCY: usize = 8;
+
+/// Multipart uploads started under the location's key prefix and never
+/// completed or aborted, with the parts each holds. Their parts are billed until
+/// they are aborted.
+pub fn list_multipart_uploads(client: &S3Client, location: &S3Location) -> Result<Vec<S3MultipartUpload>, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let uploads = list_uploads(client, &location.bucket, &location.key).await?;
+        with_parts(client, &location.bucket, uploads).await
+    })
+}
+
+/// Aborts every in-progress multipart upload under the location started more
+/// than `older_than` ago. With `options.dry_run` the uploads are only listed.
+/// Uploads that complete or are aborted elsewhere meanwhile are left out.
+pub fn abort_stale_uploads(client: &S3Client, location: &S3Location, older_than: Duration, options: &AbortOptions) -> Result<AbortReport, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let now = Utc::now();
+        let stale: Vec<S3MultipartUpload> = list_uploads(client, &location.bucket, &location.key).await?
+            .into_iter()
+            .filter(|upload| upload.is_older_than(older_than, now))
+            .collect();
+        let planned = with_parts(client, &location.bucket, stale).await?;
+        let mut report = AbortReport::from(planned, options.dry_run);
+        if options.dry_run { return Ok(report); }
+
+        let results = stream::iter(report.planned.iter().map(|upload| abort(client, &location.bucket, upload)))
+            .buffer_unordered(options.concurrency.max(1))
+            .collect::<Vec<Result<(), OperationFailure>>>().await;
+        for result in results {
+            match result {
+                Ok(()) => report.aborted += 1,
+                Err(failure) => report.failures.push(failure),
+            }
+        }
+        Ok(report)
+    })
+}
+
+pub(crate) async fn list_uploads(client: &S3Client, bucket: &str, prefix: &str) -> Result<Vec<S3MultipartUpload>, String> {
+    let mut uploads: Vec<S3MultipartUpload> = vec![];
+    let mut key_marker = None;
+    let mut upload_id_marker = None;
+    loop {
+        let output = client.list_multipart_uploads(ListMultipartUploadsRequest {
+            bucket: bucket.to_string(),
+            prefix: Some(prefix.to_string()),
+            key_marker: key_marker.clone(),
+            upload_id_marker: upload_id_marker.clone(),
+            ..ListMultipartUploadsRequest::default()
+        }).await.map_err(|e| ErrorResponse::json(e.to_string().as_str()))?;
+
+        uploads.extend(output.uploads.unwrap_or_default().iter().map(S3MultipartUpload::from));
+        if !output.is_truncated.unwrap_or(false) { break; }
+        key_marker = output.next_key_marker;
+        upload_id_marker = output.next_upload_id_marker;
+    }
+    Ok(uploads)
+}
+
+/// Adds each upload's parts, dropping uploads that have gone since they were listed.
+async fn with_parts(client: &S3Client, bucket: &str, uploads: Vec<S3MultipartUpload>) -> Result<Vec<S3MultipartUpload>, String> {
+    let listed = stream::iter(uploads.into_iter().map(|upload| async move {
+        list_parts(client, bucket, &upload).await.map(|parts| parts.map(|parts| upload.with_parts(&parts)))
+    }))
+        .buffered(LIST_PARTS_CONCURRENCY)
+        .collect::<Vec<Result<Option<S3MultipartUpload>, String>>>().await;
+    still_open(listed)
+}
+
+fn still_open(listed: Vec<Result<Option<S3MultipartUpload>, String>>) -> Result<Vec<S3MultipartUpload>, String> {
+    listed.into_iter().filter_map(Result::transpose).collect()
+}
+
+/// `None` when the upload completed or was aborted after it was listed.
+async fn list_parts(client: &S3Client, bucket: &str, upload: &S3MultipartUpload) -> Result<Option<Vec<Part>>, String> {
+    let mut parts: Vec<Part> = vec![];
+    let mut part_number_marker = None;
+    loop {
+        let output = match unless_gone(client.list_parts(ListPartsRequest {
+            bucket: bucket.to_string(),
+            key: upload.key.clone(),
+            upload_id: upload.upload_id.clone(),
+            part_number_marker,
+            ..ListPartsRequest::default()
+        }).await)? {
+            Some(output) => output,
+            None => return Ok(None),
+        };
+
+        parts.extend(output.parts.unwrap_or_default());
+        if !output.is_truncated.unwrap_or(false) { break; }
+        part_number_marker = output.next_part_number_marker;
+    }
+    Ok(Some(parts))
+}
+
+fn unless_gone<T, E: Error + 'static>(result: Result<T, RusotoError<E>>) -> Result<Option<T>, String> {
+    match result {
+        Ok(output) => Ok(Some(output)),
+        Err(e) if e.to_string().contains("NoSuchUpload") => Ok(None),
+        Err(e) => Err(ErrorResponse::json(e.to_string().as_str())),
+    }
+}
+
+async fn abort(client: &S3Client, bucket: &str, upload: &S3MultipartUpload) -> Result<(), OperationFailure> {
+    client.abort_multipart_upload(AbortMultipartUploadRequest {
+        bucket: bucket.to_string(),
+        key: upload.key.clone(),
+        upload_id: upload.upload_id.clone(),
+        ..AbortMultipartUploadRequest::default()
+    }).await
+        .map(|_| ())
+        .map_err(|e| OperationFailure {
+            bucket: bucket.to_string(),
+            key: upload.key.clone(),
+            code: None,
+            message: e.to_string(),
+        })
+}
+
+#[test]
+fn uploads_gone_since_listing_are_dropped() {
+    let gone: Result<(), RusotoError<rusoto_s3::ListPartsError>> = Err(RusotoError::Validation("NoSuchUpload: The specified upload does not exist".to_string()));
+    let failed: Result<(), RusotoError<rusoto_s3::ListPartsError>> = Err(RusotoError::Validation("AccessDenied".to_string()));
+    assert_eq!(Ok(None), unless_gone(gone));
+    assert!(unless_gone(failed).is_err());
+
+    let upload = S3MultipartUpload::from(&rusoto_s3::MultipartUpload { key: Some("k".to_string()), ..rusoto_s3::MultipartUpload::default() });
+    assert_eq!(Ok(vec![upload.clone()]), still_open(vec![Ok(None), Ok(Some(upload.clone()))]));
+    assert!(still_open(vec![Ok(Some(upload)), Err("denied".to_string())]).is_err());
+}
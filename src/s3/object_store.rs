@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rusoto_s3::{DeleteObjectRequest, S3, S3Client};
+use tokio::runtime::Runtime;
+use crate::errors::models::error_response::ErrorResponse;
+use crate::s3::download::{download_to_bytes, hash_file};
+use crate::s3::metadata::head;
+use crate::s3::models::download::DownloadOptions;
+use crate::s3::models::etag::ETagHasher;
+use crate::s3::models::object_filter::ObjectFilter;
+use crate::s3::models::object_metadata::ObjectMetadata;
+use crate::s3::models::s3_list_object::S3ListObject;
+use crate::s3::models::s3_location::{validate_bucket_name, S3Location};
+use crate::s3::models::sync::KeyFilter;
+use crate::s3::models::upload::{content_type_for, UploadOptions};
+use crate::s3::s3::s3_list;
+use crate::s3::sync::{file_e_tag, io_error, local_entries, local_path, relative_key};
+use crate::s3::upload::put_reader;
+
+/// The object operations tooling needs, so it can run against S3 or a local
+/// directory interchangeably.
+pub trait ObjectStore {
+    /// Every object whose key starts with the location's key.
+    fn list(&self, location: &S3Location) -> Result<Vec<S3ListObject>, String>;
+    fn get(&self, location: &S3Location) -> Result<Vec<u8>, String>;
+    fn put(&self, location: &S3Location, data: &[u8]) -> Result<(), String>;
+    /// Deleting an object that doesn't exist succeeds, as it does on S3.
+    fn delete(&self, location: &S3Location) -> Result<(), String>;
+    fn stat(&self, location: &S3Location) -> Result<ObjectMetadata, String>;
+}
+
+/// `ObjectStore` for callers already running on a runtime.
+#[async_trait]
+pub trait AsyncObjectStore {
+    async fn list(&self, location: &S3Location) -> Result<Vec<S3ListObject>, String>;
+    async fn get(&self, location: &S3Location) -> Result<Vec<u8>, String>;
+    async fn put(&self, location: &S3Location, data: &[u8]) -> Result<(), String>;
+    async fn delete(&self, location: &S3Location) -> Result<(), String>;
+    async fn stat(&self, location: &S3Location) -> Result<ObjectMetadata, String>;
+}
+
+pub struct S3ObjectStore {
+    client: S3Client,
+}
+
+impl S3ObjectStore {
+    pub fn new(client: S3Client) -> S3ObjectStore {
+        S3ObjectStore { client }
+    }
+}
+
+impl ObjectStore for S3ObjectStore {
+    fn list(&self, location: &S3Location) -> Result<Vec<S3ListObject>, String> {
+        let mut rt = Runtime::new().unwrap();
+        rt.block_on(AsyncObjectStore::list(self, location))
+    }
+
+    fn get(&self, location: &S3Location) -> Result<Vec<u8>, String> {
+        let mut rt = Runtime::new().unwrap();
+        rt.block_on(AsyncObjectStore::get(self, location))
+    }
+
+    fn put(&self, location: &S3Location, data: &[u8]) -> Result<(), String> {
+        let mut rt = Runtime::new().unwrap();
+        rt.block_on(AsyncObjectStore::put(self, location, data))
+    }
+
+    fn delete(&self, location: &S3Location) -> Result<(), String> {
+        let mut rt = Runtime::new().unwrap();
+        rt.block_on(AsyncObjectStore::delete(self, location))
+    }
+
+    fn stat(&self, location: &S3Location) -> Result<ObjectMetadata, String> {
+        let mut rt = Runtime::new().unwrap();
+        rt.block_on(AsyncObjectStore::stat(self, location))
+    }
+}
+
+#[async_trait]
+impl AsyncObjectStore for S3ObjectStore {
+    async fn list(&self, location: &S3Location) -> Result<Vec<S3ListObject>, String> {
+        s3_list(&self.client, &location.bucket, &location.key, &ObjectFilter::new()).await
+    }
+
+    async fn get(&self, location: &S3Location) -> Result<Vec<u8>, String> {
+        download_to_bytes(&self.client, location, &DownloadOptions::default()).await
+    }
+
+    async fn put(&self, location: &S3Location, data: &[u8]) -> Result<(), String> {
+        let mut reader = data;
+        put_reader(&self.client, &mut reader, Some(data.len() as u64), location, &UploadOptions::default()).await.map(|_| ())
+    }
+
+    async fn delete(&self, location: &S3Location) -> Result<(), String> {
+        self.client.delete_object(DeleteObjectRequest {
+            bucket: location.bucket.clone(),
+            key: location.key.clone(),
+            ..DeleteObjectRequest::default()
+        }).await.map(|_| ()).map_err(|e| ErrorResponse::json(e.to_string().as_str()))
+    }
+
+    async fn stat(&self, location: &S3Location) -> Result<ObjectMetadata, String> {
+        head(&self.client, location).await
+    }
+}
+
+/// Keeps each bucket as a directory under `root` and each key as a file path
+/// beneath it, with `/` in keys becoming directory separators. Bucket names must
+/// be valid S3 bucket names, and keys that would reach outside the bucket's
+/// directory are rejected.
+pub struct LocalObjectStore {
+    root: PathBuf,
+}
+
+impl LocalObjectStore {
+    pub fn new(root: &Path) -> LocalObjectStore {
+        LocalObjectStore { root: root.to_path_buf() }
+    }
+
+    pub fn path(&self, location: &S3Location) -> Result<PathBuf, String> {
+        local_path(&self.bucket_root(&location.bucket)?, &location.key)
+    }
+
+    fn bucket_root(&self, bucket: &str) -> Result<PathBuf, String> {
+        validate_bucket_name(bucket).map_err(|e| ErrorResponse::json(e.to_string().as_str()))?;
+        Ok(self.root.join(bucket))
+    }
+}
+
+impl ObjectStore for LocalObjectStore {
+    /// Listed objects have no ETag, as working one out means reading the whole
+    /// file; `stat` computes it for a single object.
+    fn list(&self, location: &S3Location) -> Result<Vec<S3ListObject>, String> {
+        let entries = local_entries(&self.bucket_root(&location.bucket)?, &KeyFilter::default())?;
+        Ok(entries.into_iter()
+            .filter(|(key, _)| key.starts_with(&location.key))
+            .map(|(key, entry)| listed_object(key, entry.size, entry.modified))
+            .collect())
+    }
+
+    fn get(&self, location: &S3Location) -> Result<Vec<u8>, String> {
+        std::fs::read(self.path(location)?).map_err(io_error)
+    }
+
+    fn put(&self, location: &S3Location, data: &[u8]) -> Result<(), String> {
+        let path = self.path(location)?;
+        if let Some(parent) = path.parent() { std::fs::create_dir_all(parent).map_err(io_error)?; }
+        std::fs::write(path, data).map_err(io_error)
+    }
+
+    fn delete(&self, location: &S3Location) -> Result<(), String> {
+        unless_not_found(std::fs::remove_file(self.path(location)?))
+    }
+
+    fn stat(&self, location: &S3Location) -> Result<ObjectMetadata, String> {
+        let path = self.path(location)?;
+        let metadata = std::fs::metadata(&path).map_err(io_error)?;
+        Ok(local_metadata(location, &metadata, file_e_tag(&path, None)?))
+    }
+}
+
+#[async_trait]
+impl AsyncObjectStore for LocalObjectStore {
+    async fn list(&self, location: &S3Location) -> Result<Vec<S3ListObject>, String> {
+        let bucket_root = self.bucket_root(&location.bucket)?;
+        let mut objects = vec![];
+        if tokio::fs::metadata(&bucket_root).await.is_err() { return Ok(objects); }
+
+        let mut directories = vec![bucket_root.clone()];
+        while let Some(directory) = directories.pop() {
+            let mut entries = tokio::fs::read_dir(&directory).await.map_err(io_error)?;
+            while let Some(entry) = entries.next_entry().await.map_err(io_error)? {
+                let metadata = entry.metadata().await.map_err(io_error)?;
+                if metadata.is_dir() {
+                    directories.push(entry.path());
+                } else if metadata.is_file() {
+                    let key = relative_key(&bucket_root, &entry.path());
+                    if key.starts_with(&location.key) {
+                        objects.push(listed_object(key, metadata.len(), metadata.modified().ok().map(DateTime::<Utc>::from)));
+                    }
+                }
+            }
+        }
+        objects.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(objects)
+    }
+
+    async fn get(&self, location: &S3Location) -> Result<Vec<u8>, String> {
+        tokio::fs::read(self.path(location)?).await.map_err(io_error)
+    }
+
+    async fn put(&self, location: &S3Location, data: &[u8]) -> Result<(), String> {
+        let path = self.path(location)?;
+        if let Some(parent) = path.parent() { tokio::fs::create_dir_all(parent).await.map_err(io_error)?; }
+        tokio::fs::write(path, data).await.map_err(io_error)
+    }
+
+    async fn delete(&self, location: &S3Location) -> Result<(), String> {
+        unless_not_found(tokio::fs::remove_file(self.path(location)?).await)
+    }
+
+    async fn stat(&self, location: &S3Location) -> Result<ObjectMetadata, String> {
+        let path = self.path(location)?;
+        let metadata = tokio::fs::metadata(&path).await.map_err(io_error)?;
+        let mut hasher = ETagHasher::new(None);
+        hash_file(&path, &mut hasher).await?;
+        Ok(local_metadata(location, &metadata, hasher.finish()))
+    }
+}
+
+fn listed_object(key: String, size: u64, modified: Option<DateTime<Utc>>) -> S3ListObject {
+    S3ListObject {
+        last_modified: modified.map(|m| m.to_rfc3339()).unwrap_or_default(),
+        size: size as i64,
+        e_tag: None,
+        storage_class: Some("STANDARD".to_string()),
+        key,
+    }
+}
+
+fn local_metadata(location: &S3Location, metadata: &std::fs::Metadata, e_tag: String) -> ObjectMetadata {
+    ObjectMetadata {
+        bucket: location.bucket.clone(),
+        key: location.key.clone(),
+        content_length: metadata.len() as i64,
+        content_type: Some(content_type_for(&location.key).to_string()),
+        e_tag: Some(e_tag),
+        last_modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+        storage_class: "STANDARD".to_string(),
+        server_side_encryption: None,
+        kms_key_id: None,
+        version_id: None,
+        metadata: BTreeMap::new(),
+        restore: None,
+    }
+}
+
+fn unless_not_found(result: std::io::Result<()>) -> Result<(), String> {
+    match result {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(io_error(e)),
+        _ => Ok(()),
+    }
+}
+
+#[test]
+fn local_store_round_trips_objects() {
+    let root = std::env::temp_dir().join(format!("object-store-{}", std::process::id()));
+    let store: &dyn ObjectStore = &LocalObjectStore::new(&root);
+    let location = S3Location::new("bucket", "dir/data.csv");
+
+    store.put(&location, b"a,b\n").unwrap();
+    assert_eq!(b"a,b\n".to_vec(), store.get(&location).unwrap());
+    assert_eq!(4, store.stat(&location).unwrap().content_length);
+    assert_eq!(Some("text/csv".to_string()), store.stat(&location).unwrap().content_type);
+
+    let listed = store.list(&S3Location::new("bucket", "dir/")).unwrap();
+    assert_eq!(vec!["dir/data.csv".to_string()], listed.iter().map(|o| o.key.clone()).collect::<Vec<String>>());
+    assert_eq!(None, listed[0].e_tag);
+    assert_eq!(Some(crate::s3::models::etag::compute(b"a,b\n", None)), store.stat(&location).unwrap().e_tag);
+
+    store.delete(&location).unwrap();
+    store.delete(&location).unwrap();
+    assert!(store.get(&location).is_err());
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn local_store_rejects_paths_outside_the_root() {
+    let store = LocalObjectStore::new(Path::new("/data/store"));
+    assert!(store.path(&S3Location::new("..", "etc/passwd")).is_err());
+    assert!(store.path(&S3Location::new("bucket/../..", "x")).is_err());
+    assert!(store.path(&S3Location::new("bucket", "../../etc/passwd")).is_err());
+    assert!(store.path(&S3Location::new("bucket", "/etc/passwd")).is_err());
+    assert_eq!(Path::new("/data/store/bucket/a/b"), store.path(&S3Location::new("bucket", "a/b")).unwrap());
+}
+
+#[test]
+fn async_local_store_matches_the_sync_one() {
+    let root = std::env::temp_dir().join(format!("async-object-store-{}", std::process::id()));
+    let store = LocalObjectStore::new(&root);
+    let location = S3Location::new("bucket", "dir/data.csv");
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        AsyncObjectStore::put(&store, &location, b"a,b\n").await.unwrap();
+        ObjectStore::put(&store, &S3Location::new("bucket", "dir/a/b.csv"), b"c").unwrap();
+        assert_eq!(b"a,b\n".to_vec(), AsyncObjectStore::get(&store, &location).await.unwrap());
+        let (sync_stat, async_stat) = (ObjectStore::stat(&store, &location).unwrap(), AsyncObjectStore::stat(&store, &location).await.unwrap());
+        assert_eq!((sync_stat.content_length, sync_stat.e_tag), (async_stat.content_length, async_stat.e_tag));
+        let prefix = S3Location::new("bucket", "dir/");
+        let summary = |objects: Vec<S3ListObject>| objects.into_iter().map(|o| (o.key, o.size)).collect::<Vec<(String, i64)>>();
+        assert_eq!(summary(ObjectStore::list(&store, &prefix).unwrap()), summary(AsyncObjectStore::list(&store, &prefix).await.unwrap()));
+        AsyncObjectStore::delete(&store, &location).await.unwrap();
+        AsyncObjectStore::delete(&store, &location).await.unwrap();
+        assert!(AsyncObjectStore::get(&store, &location).await.is_err());
+    });
+    std::fs::remove_dir_all(&root).unwrap();
+}
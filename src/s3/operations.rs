@@ -0,0 +1,332 @@
+use std::collections::HashSet;
+use futures::stream::{self, StreamExt};
+use rusoto_s3::{AbortMultipartUploadRequest, CompleteMultipartUploadRequest, CompletedMultipartUpload, CompletedPart};
+use rusoto_s3::{CopyObjectRequest, CreateMultipartUploadRequest, Delete, DeleteObjectsRequest, HeadObjectRequest};
+use rusoto_s3::{ObjectIdentifier, UploadPartCopyRequest, S3, S3Client};
+use tokio::runtime::Runtime;
+use crate::errors::models::error_response::ErrorResponse;
+use crate::s3::download::byte_ranges;
+use crate::s3::models::object_filter::ObjectFilter;
+use crate::s3::models::operation::{OperationFailure, OperationOptions, OperationReport, PlannedOperation};
+use crate::s3::models::s3_location::S3Location;
+use crate::s3::models::upload::{url_encode, MAX_PARTS};
+use crate::s3::s3::s3_list;
+
+pub const MAX_COPY_OBJECT_SIZE: i64 = 5 * 1024 * 1024 * 1024;
+pub const MAX_DELETE_BATCH: usize = 1000;
+
+pub fn cp(client: &S3Client, source: &S3Location, destination: &S3Location, options: &OperationOptions) -> Result<OperationReport, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let planned = plan_copy(client, source, destination, options).await?;
+        Ok(execute(client, planned, options).await)
+    })
+}
+
+/// Copies then deletes the sources. A source is only deleted once its copy has succeeded.
+/// Moves whose destination overlaps the source are rejected, as the deletes could
+/// remove objects the copies have just written.
+pub fn mv(client: &S3Client, source: &S3Location, destination: &S3Location, options: &OperationOptions) -> Result<OperationReport, String> {
+    if overlaps(source, destination, options.recursive) {
+        return Err(ErrorResponse::json(format!("cannot move {} into overlapping destination {}", source, destination).as_str()));
+    }
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let mut planned = plan_copy(client, source, destination, options).await?;
+        let mut deletes = planned.iter().flat_map(|operation| match operation {
+            PlannedOperation::Copy { source_bucket, source_key, .. } => Some(PlannedOperation::Delete { bucket: source_bucket.clone(), key: source_key.clone() }),
+            _ => None,
+        }).collect::<Vec<PlannedOperation>>();
+        planned.append(&mut deletes);
+        Ok(execute(client, planned, options).await)
+    })
+}
+
+pub fn rm(client: &S3Client, location: &S3Location, options: &OperationOptions) -> Result<OperationReport, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let planned = plan_delete(client, location, options).await?;
+        Ok(execute(client, planned, options).await)
+    })
+}
+
+pub(crate) async fn plan_copy(client: &S3Client, source: &S3Location, destination: &S3Location, options: &OperationOptions) -> Result<Vec<PlannedOperation>, String> {
+    if options.recursive {
+        let source_prefix = as_prefix(&source.key);
+        let destination_prefix = as_prefix(&destination.key);
+        let objects = s3_list(client, &source.bucket, &source_prefix, &ObjectFilter::new()).await?;
+        Ok(objects.iter().map(|object| PlannedOperation::Copy {
+            source_bucket: source.bucket.clone(),
+            source_key: object.key.clone(),
+            source_version_id: None,
+            destination_bucket: destination.bucket.clone(),
+            destination_key: format!("{}{}", destination_prefix, &object.key[source_prefix.len()..]),
+            size: object.size,
+        }).collect())
+    } else {
+        let head = client.head_object(HeadObjectRequest {
+            bucket: source.bucket.clone(),
+            key: source.key.clone(),
+            ..HeadObjectRequest::default()
+        }).await.map_err(|e| ErrorResponse::json(e.to_string().as_str()))?;
+        Ok(vec![PlannedOperation::Copy {
+            source_bucket: source.bucket.clone(),
+            source_key: source.key.clone(),
+            source_version_id: None,
+            destination_bucket: destination.bucket.clone(),
+            destination_key: single_destination_key(&source.key, &destination.key),
+            size: head.content_length.unwrap_or(0),
+        }])
+    }
+}
+
+pub(crate) async fn plan_delete(client: &S3Client, location: &S3Location, options: &OperationOptions) -> Result<Vec<PlannedOperation>, String> {
+    if options.recursive {
+        let objects = s3_list(client, &location.bucket, &as_prefix(&location.key), &ObjectFilter::new()).await?;
+        Ok(objects.iter().map(|object| PlannedOperation::Delete {
+            bucket: location.bucket.clone(),
+            key: object.key.clone(),
+        }).collect())
+    } else {
+        Ok(vec![PlannedOperation::Delete { bucket: location.bucket.clone(), key: location.key.clone() }])
+    }
+}
+
+/// Runs the copies in `planned` concurrently, then the deletes in batches. Deletes
+/// of keys whose copy failed are skipped and reported as failures, so a move never
+/// loses data. With `options.dry_run` nothing is executed.
+pub(crate) async fn execute(client: &S3Client, planned: Vec<PlannedOperation>, options: &OperationOptions) -> OperationReport {
+    let mut report = OperationReport { dry_run: options.dry_run, ..OperationReport::default() };
+    if options.dry_run {
+        report.planned = planned;
+        return report;
+    }
+
+    let copy_results = stream::iter(planned.iter().filter(|operation| matches!(operation, PlannedOperation::Copy { .. }))
+        .map(|operation| execute_copy(client, operation, options)))
+        .buffer_unordered(options.concurrency.max(1))
+        .collect::<Vec<Result<(), OperationFailure>>>().await;
+
+    copy_results.into_iter().for_each(|result| match result {
+        Ok(()) => report.completed += 1,
+        Err(failure) => report.failures.push(failure),
+    });
+    let failed_destinations: HashSet<(&str, &str)> = report.failures.iter().map(|f| (f.bucket.as_str(), f.key.as_str())).collect();
+    let failed_sources: HashSet<(String, String)> = planned.iter().flat_map(|operation| match operation {
+        PlannedOperation::Copy { source_bucket, source_key, destination_bucket, destination_key, .. }
+            if failed_destinations.contains(&(destination_bucket.as_str(), destination_key.as_str())) => Some((source_bucket.clone(), source_key.clone())),
+        _ => None,
+    }).collect();
+
+    let deletes = planned.iter().flat_map(|operation| match operation {
+        PlannedOperation::Delete { bucket, key } => Some((bucket.clone(), key.clone())),
+        _ => None,
+    }).collect::<Vec<(String, String)>>();
+    let (skipped, deletes): (Vec<(String, String)>, Vec<(String, String)>) = deletes.into_iter().partition(|d| failed_sources.contains(d));
+    skipped.into_iter().for_each(|(bucket, key)| report.failures.push(OperationFailure {
+        bucket,
+        key,
+        code: None,
+        message: "Not deleted because the copy failed".to_string(),
+    }));
+
+    let mut buckets = deletes.iter().map(|(bucket, _)| bucket.clone()).collect::<Vec<String>>();
+    buckets.sort();
+    buckets.dedup();
+    for bucket in buckets {
+        let keys = deletes.iter().filter(|(b, _)| *b == bucket).map(|(_, k)| k.clone()).collect::<Vec<String>>();
+        for batch in keys.chunks(MAX_DELETE_BATCH) {
+            let (completed, mut failures) = delete_batch(client, &bucket, batch).await;
+            report.completed += completed;
+            report.failures.append(&mut failures);
+        }
+    }
+    report.planned = planned;
+    report
+}
+
+pub(crate) async fn execute_copy(client: &S3Client, operation: &PlannedOperation, options: &OperationOptions) -> Result<(), OperationFailure> {
+    if let PlannedOperation::Copy { source_bucket, source_key, source_version_id, destination_bucket, destination_key, size } = operation {
+        let result = if *size > MAX_COPY_OBJECT_SIZE {
+            multipart_copy(client, operation, options.copy_part_size).await
+        } else {
+            client.copy_object(CopyObjectRequest {
+                bucket: destination_bucket.clone(),
+                key: destination_key.clone(),
+                copy_source: copy_source(source_bucket, source_key, source_version_id.as_deref()),
+                ..CopyObjectRequest::default()
+            }).await.map(|_| ()).map_err(|e| e.to_string())
+        };
+        result.map_err(|message| OperationFailure {
+            bucket: destination_bucket.clone(),
+            key: destination_key.clone(),
+            code: None,
+            message,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+async fn multipart_copy(client: &S3Client, operation: &PlannedOperation, part_size: u64) -> Result<(), String> {
+    let (source_bucket, source_key, source_version_id, destination_bucket, destination_key, size) = match operation {
+        PlannedOperation::Copy { source_bucket, source_key, source_version_id, destination_bucket, destination_key, size } =>
+            (source_bucket, source_key, source_version_id, destination_bucket, destination_key, *size as u64),
+        _ => return Ok(()),
+    };
+    let head = client.head_object(HeadObjectRequest {
+        bucket: source_bucket.to_string(),
+        key: source_key.to_string(),
+        version_id: source_version_id.clone(),
+        ..HeadObjectRequest::default()
+    }).await.map_err(|e| e.to_string())?;
+    let upload_id = client.create_multipart_upload(CreateMultipartUploadRequest {
+        bucket: destination_bucket.to_string(),
+        key: destination_key.to_string(),
+        content_type: head.content_type,
+        metadata: head.metadata,
+        storage_class: head.storage_class,
+        server_side_encryption: head.server_side_encryption,
+        ssekms_key_id: head.ssekms_key_id,
+        ..CreateMultipartUploadRequest::default()
+    }).await.map_err(|e| e.to_string())?.upload_id
+        .ok_or_else(|| format!("no upload id returned for {}/{}", destination_bucket, destination_key))?;
+
+    let part_size = part_size.max(size.div_ceil(MAX_PARTS));
+    let source = copy_source(source_bucket, source_key, source_version_id.as_deref());
+    let copied = copy_parts(client, &source, destination_bucket, destination_key, &upload_id, size, part_size).await;
+    if copied.is_err() {
+        let _ = client.abort_multipart_upload(AbortMultipartUploadRequest {
+            bucket: destination_bucket.to_string(),
+            key: destination_key.to_string(),
+            upload_id,
+            ..AbortMultipartUploadRequest::default()
+        }).await;
+    }
+    copied
+}
+
+/// Copies every part and completes the upload; the caller aborts it on failure.
+async fn copy_parts(client: &S3Client, source: &str, destination_bucket: &str, destination_key: &str, upload_id: &str, size: u64, part_size: u64) -> Result<(), String> {
+    let mut parts: Vec<CompletedPart> = vec![];
+    for (i, (start, end)) in byte_ranges(0, size, part_size).into_iter().enumerate() {
+        let output = client.upload_part_copy(UploadPartCopyRequest {
+            bucket: destination_bucket.to_string(),
+            key: destination_key.to_string(),
+            upload_id: upload_id.to_string(),
+            part_number: i as i64 + 1,
+            copy_source: source.to_string(),
+            copy_source_range: Some(format!("bytes={}-{}", start, end)),
+            ..UploadPartCopyRequest::default()
+        }).await.map_err(|e| e.to_string())?;
+        parts.push(CompletedPart {
+            e_tag: output.copy_part_result.and_then(|r| r.e_tag),
+            part_number: Some(i as i64 + 1),
+        });
+    }
+
+    client.complete_multipart_upload(CompleteMultipartUploadRequest {
+        bucket: destination_bucket.to_string(),
+        key: destination_key.to_string(),
+        upload_id: upload_id.to_string(),
+        multipart_upload: Some(CompletedMultipartUpload { parts: Some(parts) }),
+        ..CompleteMultipartUploadRequest::default()
+    }).await.map(|_| ()).map_err(|e| e.to_string())
+}
+
+pub(crate) async fn delete_batch(client: &S3Client, bucket: &str, keys: &[String]) -> (usize, Vec<OperationFailure>) {
+    let output = client.delete_objects(DeleteObjectsRequest {
+        bucket: bucket.to_string(),
+        delete: Delete {
+            objects: keys.iter().map(|key| ObjectIdentifier { key: key.clone(), version_id: None }).collect(),
+            quiet: Some(true),
+        },
+        ..DeleteObjectsRequest::default()
+    }).await;
+
+    match output {
+        Ok(output) => {
+            let failures = output.errors.unwrap_or_default().into_iter().map(|e| OperationFailure {
+                bucket: bucket.to_string(),
+                key: e.key.unwrap_or_default(),
+                code: e.code,
+                message: e.message.unwrap_or_default(),
+            }).collect::<Vec<OperationFailure>>();
+            (keys.len() - failures.len(), failures)
+        },
+        Err(e) => (0, keys.iter().map(|key| OperationFailure {
+            bucket: bucket.to_string(),
+            key: key.clone(),
+            code: None,
+            message: e.to_string(),
+        }).collect()),
+    }
+}
+
+/// The `x-amz-copy-source` value: bucket and key, with each key segment URL encoded,
+/// and the version when copying a specific version.
+pub(crate) fn copy_source(bucket: &str, key: &str, version_id: Option<&str>) -> String {
+    let source = format!("{}/{}", bucket, key.split('/').map(url_encode).collect::<Vec<String>>().join("/"));
+    match version_id {
+        Some(version_id) => format!("{}?versionId={}", source, url_encode(version_id)),
+        None => source,
+    }
+}
+
+/// Treats a key as a directory: empty stays empty, otherwise it gains a trailing slash.
+pub(crate) fn as_prefix(key: &str) -> String {
+    if key.is_empty() || key.ends_with('/') { key.to_string() } else { format!("{}/", key) }
+}
+
+/// Whether a move from `source` to `destination` would write into what it reads
+/// from: the same key, or for recursive moves, nested prefixes in the same bucket.
+fn overlaps(source: &S3Location, destination: &S3Location, recursive: bool) -> bool {
+    if source.bucket != destination.bucket { return false; }
+    if recursive {
+        let source_prefix = as_prefix(&source.key);
+        let destination_prefix = as_prefix(&destination.key);
+        source_prefix.starts_with(&destination_prefix) || destination_prefix.starts_with(&source_prefix)
+    } else {
+        single_destination_key(&source.key, &destination.key) == source.key
+    }
+}
+
+fn single_destination_key(source_key: &str, destination_key: &str) -> String {
+    if destination_key.is_empty() || destination_key.ends_with('/') {
+        format!("{}{}", destination_key, source_key.rsplit('/').next().unwrap_or(source_key))
+    } else {
+        destination_key.to_string()
+    }
+}
+
+#[test]
+fn copy_source_encodes_key_segments() {
+    assert_eq!("bucket/a%20b/c%2Bd.csv", copy_source("bucket", "a b/c+d.csv", None));
+    assert_eq!("bucket/a.csv?versionId=3HL4kqtJ", copy_source("bucket", "a.csv", Some("3HL4kqtJ")));
+}
+
+#[test]
+fn keys_become_prefixes() {
+    assert_eq!("", as_prefix(""));
+    assert_eq!("a/", as_prefix("a"));
+    assert_eq!("a/", as_prefix("a/"));
+}
+
+#[test]
+fn copying_into_a_prefix_keeps_the_file_name() {
+    assert_eq!("out/part-0.csv", single_destination_key("in/part-0.csv", "out/"));
+    assert_eq!("part-0.csv", single_destination_key("in/part-0.csv", ""));
+    assert_eq!("out/renamed.csv", single_destination_key("in/part-0.csv", "out/renamed.csv"));
+}
+
+#[test]
+fn moves_into_overlapping_prefixes_are_detected() {
+    let location = |path: &str| S3Location::from(path).unwrap();
+    assert!(overlaps(&location("s3://b/a/"), &location("s3://b/a/x/"), true));
+    assert!(overlaps(&location("s3://b/a/x"), &location("s3://b/a"), true));
+    assert!(overlaps(&location("s3://b/"), &location("s3://b/a/"), true));
+    assert!(!overlaps(&location("s3://b/a/"), &location("s3://b/ab/"), true));
+    assert!(!overlaps(&location("s3://b/a/"), &location("s3://c/a/x/"), true));
+    assert!(overlaps(&location("s3://b/a/k.csv"), &location("s3://b/a/"), false));
+    assert!(!overlaps(&location("s3://b/a/k.csv"), &location("s3://b/a/x/"), false));
+}
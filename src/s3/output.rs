@@ -0,0 +1,20 @@
+use chrono::Utc;
+use rusoto_s3::S3Client;
+use tokio::runtime::Runtime;
+use crate::s3::models::object_filter::ObjectFilter;
+use crate::s3::models::output_check::{OutputRules, OutputVerdict};
+use crate::s3::models::s3_location::S3Location;
+use crate::s3::operations::as_prefix;
+use crate::s3::s3::s3_list;
+
+/// Checks whether a job's output prefix is complete: success marker present, enough
+/// objects and bytes, no `_temporary` directories left behind and recent enough.
+/// The verdict can be attached to a pipeline with `Pipeline::with_output_check`.
+pub fn check_output(client: &S3Client, location: &S3Location, rules: &OutputRules) -> Result<OutputVerdict, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let prefix = as_prefix(&location.key);
+        let objects = s3_list(client, &location.bucket, &prefix, &ObjectFilter::new()).await?;
+        Ok(OutputVerdict::evaluate(&location.to_string(), &prefix, &objects, rules, Utc::now()))
+    })
+}
@@ -0,0 +1,30 @@
+use chrono::NaiveDateTime;
+use rusoto_s3::S3Client;
+use tokio::runtime::Runtime;
+use crate::s3::models::object_filter::ObjectFilter;
+use crate::s3::models::partition::{Partition, PartitionSet};
+use crate::s3::models::s3_location::S3Location;
+use crate::s3::operations::as_prefix;
+use crate::s3::s3::s3_list;
+
+/// Discovers the Hive-style `name=value` partitions under a table location.
+pub fn partitions(client: &S3Client, location: &S3Location) -> Result<PartitionSet, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async { discover(client, location).await })
+}
+
+pub fn latest_partition(client: &S3Client, location: &S3Location) -> Result<Option<Partition>, String> {
+    partitions(client, location).map(|set| set.latest().cloned())
+}
+
+/// The days (or hours, with an hour column) between `from` and `to` inclusive
+/// that have no partition under the table location.
+pub fn partition_gaps(client: &S3Client, location: &S3Location, date_column: &str, hour_column: Option<&str>, from: NaiveDateTime, to: NaiveDateTime) -> Result<Vec<NaiveDateTime>, String> {
+    partitions(client, location).map(|set| set.gaps(date_column, hour_column, from, to))
+}
+
+pub(crate) async fn discover(client: &S3Client, location: &S3Location) -> Result<PartitionSet, String> {
+    let table_prefix = as_prefix(&location.key);
+    let objects = s3_list(client, &location.bucket, &table_prefix, &ObjectFilter::new()).await?;
+    Ok(PartitionSet::discover(&table_prefix, &objects))
+}
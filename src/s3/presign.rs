@@ -0,0 +1,80 @@
+use chrono::Utc;
+use rusoto_core::Region;
+use rusoto_credential::{AwsCredentials, ProvideAwsCredentials};
+use rusoto_s3::{GetObjectRequest, PutObjectRequest};
+use rusoto_s3::util::{PreSignedRequest, PreSignedRequestOption};
+use tokio::runtime::Runtime;
+use crate::errors::models::error_response::ErrorResponse;
+use crate::s3::models::presign::{credential_scope, post_policy, sign, signing_key, validate_expiry};
+use crate::s3::models::presign::{PostPolicyOptions, PresignOptions, PresignedPost};
+use crate::s3::models::s3_location::{dns_suffix, partition_for_region, S3Location};
+
+/// A temporary download link. Pass the credentials provider and region the
+/// client was built with; the `response_*` options override the headers S3
+/// returns with the object.
+pub fn presign_get<P: ProvideAwsCredentials>(provider: &P, region: &Region, location: &S3Location, options: &PresignOptions) -> Result<String, String> {
+    validate_expiry(options.expires_in).map_err(|e| ErrorResponse::json(e.as_str()))?;
+    let credentials = credentials(provider)?;
+    let request = GetObjectRequest {
+        bucket: location.bucket.clone(),
+        key: location.key.clone(),
+        response_content_type: options.response_content_type.clone(),
+        response_content_disposition: options.response_content_disposition.clone(),
+        response_cache_control: options.response_cache_control.clone(),
+        ..GetObjectRequest::default()
+    };
+    Ok(request.get_presigned_url(region, &credentials, &PreSignedRequestOption { expires_in: options.expires_in }))
+}
+
+/// A temporary upload link for a single PUT. With `options.content_type` the
+/// uploader must send exactly that `Content-Type`.
+pub fn presign_put<P: ProvideAwsCredentials>(provider: &P, region: &Region, location: &S3Location, options: &PresignOptions) -> Result<String, String> {
+    validate_expiry(options.expires_in).map_err(|e| ErrorResponse::json(e.as_str()))?;
+    let credentials = credentials(provider)?;
+    let request = PutObjectRequest {
+        bucket: location.bucket.clone(),
+        key: location.key.clone(),
+        content_type: options.content_type.clone(),
+        ..PutObjectRequest::default()
+    };
+    Ok(request.get_presigned_url(region, &credentials, &PreSignedRequestOption { expires_in: options.expires_in }))
+}
+
+/// A signed POST policy for browser form uploads to the location. A location
+/// ending in `/` accepts any file name under that prefix.
+pub fn presign_post<P: ProvideAwsCredentials>(provider: &P, region: &Region, location: &S3Location, options: &PostPolicyOptions) -> Result<PresignedPost, String> {
+    validate_expiry(options.expires_in).map_err(|e| ErrorResponse::json(e.as_str()))?;
+    let credentials = credentials(provider)?;
+    let now = Utc::now();
+    let credential = credential_scope(credentials.aws_access_key_id(), &now, region.name());
+    let (mut fields, policy) = post_policy(&location.bucket, &location.key, &credential, &now, credentials.token().as_deref(), options);
+
+    let encoded_policy = base64::encode(policy.to_string());
+    let key = signing_key(credentials.aws_secret_access_key(), &now, region.name(), "s3");
+    fields.insert("x-amz-signature".to_string(), sign(&key, &encoded_policy));
+    fields.insert("policy".to_string(), encoded_policy);
+
+    Ok(PresignedPost { url: post_url(region, &location.bucket), fields })
+}
+
+fn credentials<P: ProvideAwsCredentials>(provider: &P) -> Result<AwsCredentials, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async { provider.credentials().await })
+        .map_err(|e| ErrorResponse::json(e.to_string().as_str()))
+}
+
+fn post_url(region: &Region, bucket: &str) -> String {
+    match region {
+        Region::Custom { endpoint, .. } => format!("{}/{}", endpoint.trim_end_matches('/'), bucket),
+        Region::UsEast1 => format!("https://{}.s3.amazonaws.com/", bucket),
+        _ => format!("https://{}.s3.{}.{}/", bucket, region.name(), dns_suffix(partition_for_region(region.name()))),
+    }
+}
+
+#[test]
+fn post_url_follows_region() {
+    assert_eq!("https://b.s3.amazonaws.com/", post_url(&Region::UsEast1, "b"));
+    assert_eq!("https://b.s3.eu-west-1.amazonaws.com/", post_url(&Region::EuWest1, "b"));
+    assert_eq!("https://b.s3.cn-north-1.amazonaws.com.cn/", post_url(&Region::CnNorth1, "b"));
+    assert_eq!("http://localhost:9000/b", post_url(&Region::Custom { name: "local".to_string(), endpoint: "http://localhost:9000/".to_string() }, "b"));
+}
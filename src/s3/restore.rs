@@ -0,0 +1,109 @@
+use std::time::Duration;
+use futures::stream::{self, StreamExt};
+use rusoto_s3::{GlacierJobParameters, RestoreObjectRequest, RestoreRequest, S3, S3Client};
+use tokio::runtime::Runtime;
+use tokio::time::{delay_until, Instant};
+use crate::errors::models::error_response::ErrorResponse;
+use crate::s3::metadata::head;
+use crate::s3::models::object_filter::ObjectFilter;
+use crate::s3::models::operation::OperationFailure;
+use crate::s3::models::restore::{is_archive_storage_class, RestoreObjectStatus, RestoreOptions, RestoreRequestOutcome};
+use crate::s3::models::restore::{RestoreRequestResult, RestoreState, RestoreStatusReport};
+use crate::s3::models::s3_location::S3Location;
+use crate::s3::operations::as_prefix;
+use crate::s3::s3::s3_list;
+
+/// Requests a temporary restore of every GLACIER and DEEP_ARCHIVE object at the
+/// location (or under it, with `options.recursive`), spacing requests out to
+/// `options.requests_per_second`.
+pub fn restore(client: &S3Client, location: &S3Location, options: &RestoreOptions) -> Result<Vec<RestoreRequestResult>, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let (archived, not_archived): (Vec<(String, String)>, Vec<(String, String)>) = archived_candidates(client, location, options).await?
+            .into_iter()
+            .partition(|(_, storage_class)| is_archive_storage_class(storage_class));
+        let start = Instant::now();
+        let period = options.requests_per_second.filter(|r| *r > 0).map(|r| Duration::from_secs(1) / r);
+
+        let mut results = stream::iter(archived.into_iter().enumerate().map(|(i, (key, _))| {
+            let not_before = period.map(|p| start + p * i as u32);
+            async move {
+                if let Some(not_before) = not_before { delay_until(not_before).await; }
+                request_restore(client, &location.bucket, key, options).await
+            }
+        }))
+            .buffer_unordered(options.concurrency.max(1))
+            .collect::<Vec<RestoreRequestResult>>().await;
+        results.extend(not_archived.into_iter().map(|(key, _)| RestoreRequestResult { key, outcome: RestoreRequestOutcome::NotArchived, message: None }));
+        Ok(results)
+    })
+}
+
+/// Reports the restore state of every archived object at or under the location,
+/// from the `x-amz-restore` header of a HEAD request per object. Objects whose
+/// HEAD fails are listed as failures rather than failing the whole report.
+pub fn restore_status(client: &S3Client, location: &S3Location, options: &RestoreOptions) -> Result<RestoreStatusReport, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let objects = archived_candidates(client, location, options).await?;
+        let results = stream::iter(objects.into_iter()
+            .filter(|(_, storage_class)| is_archive_storage_class(storage_class))
+            .map(|(key, _)| async move {
+                let metadata = head(client, &S3Location { key: key.clone(), ..location.clone() }).await
+                    .map_err(|message| OperationFailure { bucket: location.bucket.clone(), key, code: None, message })?;
+                Ok(RestoreObjectStatus {
+                    state: RestoreState::from(&metadata.storage_class, &metadata.restore),
+                    expiry: metadata.restore.as_ref().and_then(|r| r.expiry),
+                    key: metadata.key,
+                    storage_class: metadata.storage_class,
+                })
+            }))
+            .buffer_unordered(options.concurrency.max(1))
+            .collect::<Vec<Result<RestoreObjectStatus, OperationFailure>>>().await;
+
+        let (mut statuses, mut failures) = (vec![], vec![]);
+        for result in results {
+            match result {
+                Ok(status) => statuses.push(status),
+                Err(failure) => failures.push(failure),
+            }
+        }
+        statuses.sort_by(|a, b| a.key.cmp(&b.key));
+        failures.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(RestoreStatusReport::from(statuses, failures))
+    })
+}
+
+async fn archived_candidates(client: &S3Client, location: &S3Location, options: &RestoreOptions) -> Result<Vec<(String, String)>, String> {
+    if options.recursive {
+        let objects = s3_list(client, &location.bucket, &as_prefix(&location.key), &ObjectFilter::new()).await?;
+        Ok(objects.into_iter()
+            .map(|o| (o.key, o.storage_class.unwrap_or_else(|| "STANDARD".to_string())))
+            .collect())
+    } else {
+        let metadata = head(client, location).await?;
+        Ok(vec![(metadata.key, metadata.storage_class)])
+    }
+}
+
+async fn request_restore(client: &S3Client, bucket: &str, key: String, options: &RestoreOptions) -> RestoreRequestResult {
+    let result = client.restore_object(RestoreObjectRequest {
+        bucket: bucket.to_string(),
+        key: key.clone(),
+        restore_request: Some(RestoreRequest {
+            days: Some(options.days),
+            glacier_job_parameters: Some(GlacierJobParameters { tier: options.tier.as_str().to_string() }),
+            ..RestoreRequest::default()
+        }),
+        ..RestoreObjectRequest::default()
+    }).await;
+
+    match result {
+        Ok(_) => RestoreRequestResult { key, outcome: RestoreRequestOutcome::Initiated, message: None },
+        Err(e) => {
+            let message = e.to_string();
+            let outcome = if message.contains("RestoreAlreadyInProgress") { RestoreRequestOutcome::AlreadyInProgress } else { RestoreRequestOutcome::Failed };
+            RestoreRequestResult { key, outcome, message: Some(ErrorResponse::json(message.as_str())) }
+        }
+    }
+}
@@ -3,20 +3,37 @@ use rusoto_s3::{ListObjectsV2Request, S3, S3Client};
 use crate::s3::models::s3_list_object::S3ListObject;
 use crate::errors::models::error_response::ErrorResponse;
 use crate::s3::models::s3_location::S3Location;
+use crate::s3::models::object_filter::ObjectFilter;
+use crate::s3::models::disk_usage::DiskUsage;
 
 
 pub fn ls(client: &S3Client, path: &str) -> Result<Vec<S3ListObject>, String> {
+    ls_filtered(client, path, &ObjectFilter::new())
+}
+
+pub fn ls_filtered(client: &S3Client, path: &str, filter: &ObjectFilter) -> Result<Vec<S3ListObject>, String> {
     let mut rt  = Runtime::new().unwrap();
 
     match S3Location::from(path) {
         Ok(location) => rt.block_on(async {
-            s3_list(&client, location.bucket.as_str(), location.key.as_str()).await
+            s3_list(&client, location.bucket.as_str(), location.key.as_str(), filter).await
         }),
-        Err(_e) => Err(ErrorResponse::json(_e.as_str())),
+        Err(_e) => Err(ErrorResponse::json(_e.to_string().as_str())),
+    }
+}
+
+pub fn du(client: &S3Client, path: &str, depth: usize) -> Result<Vec<DiskUsage>, String> {
+    let mut rt  = Runtime::new().unwrap();
+
+    match S3Location::from(path) {
+        Ok(location) => rt.block_on(async {
+            s3_list(&client, location.bucket.as_str(), location.key.as_str(), &ObjectFilter::new()).await
+        }).map(|objects| DiskUsage::summarise(location.key.as_str(), &objects, depth)),
+        Err(_e) => Err(ErrorResponse::json(_e.to_string().as_str())),
     }
 }
 
-async fn s3_list(client: &S3Client, bucket: &str, prefix: &str) -> Result<Vec<S3ListObject>, String> {
+pub(crate) async fn s3_list(client: &S3Client, bucket: &str, prefix: &str, filter: &ObjectFilter) -> Result<Vec<S3ListObject>, String> {
     fn build_s3_request(bucket: &str, prefix: &str, continuation_token: Option<String>) -> ListObjectsV2Request {
         ListObjectsV2Request {
             bucket: String::from(bucket),
@@ -26,12 +43,12 @@ async fn s3_list(client: &S3Client, bucket: &str, prefix: &str) -> Result<Vec<S3
         }
     }
 
-    async fn rec(acc: &mut Vec<S3ListObject>, next_continuation_token: Option<String>, client: &S3Client, bucket: &str, prefix: &str) -> Result<Option<String>, String> {
+    async fn rec(acc: &mut Vec<S3ListObject>, next_continuation_token: Option<String>, client: &S3Client, bucket: &str, prefix: &str, filter: &ObjectFilter) -> Result<Option<String>, String> {
         match client.list_objects_v2(build_s3_request(bucket, prefix, next_continuation_token)).await {
             Ok(l) => {
                 match &l.contents {
                     Some(c) => {
-                        let mut x = c.iter().map(|i| S3ListObject::from(i)).collect();
+                        let mut x = c.iter().map(|i| S3ListObject::from(i)).filter(|o| filter.matches(o)).collect();
                         acc.append(&mut x);
                         Ok(l.next_continuation_token)
                     },
@@ -46,7 +63,7 @@ async fn s3_list(client: &S3Client, bucket: &str, prefix: &str) -> Result<Vec<S3
     let mut next_continuation_token = None;
     let mut errors = None;
     loop {
-        match rec(&mut list, next_continuation_token, client, bucket, prefix).await {
+        match rec(&mut list, next_continuation_token, client, bucket, prefix, filter).await {
             Ok(token) => next_continuation_token = token,
             Err(_e) => {
                 errors = Some(_e);
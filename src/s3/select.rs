@@ -0,0 +1,121 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use futures::stream::{Stream, StreamExt};
+use rusoto_s3::{Progress, RequestProgress, S3, S3Client, SelectObjectContentEventStreamItem, SelectObjectContentRequest, Stats};
+use tokio::runtime::Runtime;
+use crate::errors::models::error_response::ErrorResponse;
+use crate::s3::models::s3_location::S3Location;
+use crate::s3::models::select::{InputFormat, OutputFormat, RecordBuffer, SelectEvent, SelectStats};
+
+type EventStream = Pin<Box<dyn Stream<Item = Result<SelectObjectContentEventStreamItem, String>> + Send>>;
+
+/// Runs an S3 Select SQL expression against a single object. The results stream
+/// back as they are produced: records one per item, interleaved with progress
+/// and stats events.
+pub fn select(client: &S3Client, location: &S3Location, sql: &str, input_format: &InputFormat, output_format: &OutputFormat) -> Result<SelectResults, String> {
+    let mut rt = Runtime::new().unwrap();
+    let output = rt.block_on(async {
+        client.select_object_content(SelectObjectContentRequest {
+            bucket: location.bucket.clone(),
+            key: location.key.clone(),
+            expression: sql.to_string(),
+            expression_type: "SQL".to_string(),
+            input_serialization: input_format.serialization(),
+            output_serialization: output_format.serialization(),
+            request_progress: Some(RequestProgress { enabled: Some(true) }),
+            ..SelectObjectContentRequest::default()
+        }).await
+    }).map_err(|e| ErrorResponse::json(e.to_string().as_str()))?;
+
+    let events: EventStream = match output.payload {
+        Some(payload) => Box::pin(payload.map(|item| item.map_err(|e| e.to_string()))),
+        None => Box::pin(futures::stream::empty()),
+    };
+    Ok(SelectResults { rt, events, buffer: RecordBuffer::for_output(output_format), queued: VecDeque::new(), done: false })
+}
+
+pub struct SelectResults {
+    rt: Runtime,
+    events: EventStream,
+    buffer: RecordBuffer,
+    queued: VecDeque<SelectEvent>,
+    done: bool,
+}
+
+impl SelectResults {
+    /// Just the records, dropping progress and stats events.
+    pub fn records(self) -> impl Iterator<Item = Result<String, String>> {
+        self.filter_map(|event| match event {
+            Ok(SelectEvent::Record { record }) => Some(Ok(record)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+    }
+
+    fn queue(&mut self, item: SelectObjectContentEventStreamItem) {
+        match item {
+            SelectObjectContentEventStreamItem::Records(records) => {
+                let payload = records.payload.unwrap_or_default();
+                let parsed = self.buffer.push(&payload);
+                self.queued.extend(parsed.into_iter().map(|record| SelectEvent::Record { record }));
+            },
+            SelectObjectContentEventStreamItem::Progress(progress) => {
+                self.queued.push_back(SelectEvent::Progress { stats: progress.details.map(from_progress).unwrap_or_default() });
+            },
+            SelectObjectContentEventStreamItem::Stats(stats) => {
+                self.queued.push_back(SelectEvent::Stats { stats: stats.details.map(from_stats).unwrap_or_default() });
+            },
+            SelectObjectContentEventStreamItem::End(_) => {
+                self.flush();
+                self.queued.push_back(SelectEvent::End);
+            },
+            SelectObjectContentEventStreamItem::Cont(_) => {},
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(record) = self.buffer.finish() {
+            self.queued.push_back(SelectEvent::Record { record });
+        }
+    }
+}
+
+impl Iterator for SelectResults {
+    type Item = Result<SelectEvent, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.queued.pop_front() { return Some(Ok(event)); }
+            if self.done { return None; }
+
+            let events = &mut self.events;
+            match self.rt.block_on(async { events.next().await }) {
+                Some(Ok(item)) => self.queue(item),
+                Some(Err(e)) => {
+                    self.done = true;
+                    return Some(Err(ErrorResponse::json(e.as_str())));
+                },
+                None => {
+                    self.done = true;
+                    self.flush();
+                },
+            }
+        }
+    }
+}
+
+fn from_progress(progress: Progress) -> SelectStats {
+    SelectStats {
+        bytes_scanned: progress.bytes_scanned.unwrap_or(0),
+        bytes_processed: progress.bytes_processed.unwrap_or(0),
+        bytes_returned: progress.bytes_returned.unwrap_or(0),
+    }
+}
+
+fn from_stats(stats: Stats) -> SelectStats {
+    SelectStats {
+        bytes_scanned: stats.bytes_scanned.unwrap_or(0),
+        bytes_processed: stats.bytes_processed.unwrap_or(0),
+        bytes_returned: stats.bytes_returned.unwrap_or(0),
+    }
+}
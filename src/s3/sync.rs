@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use rusoto_s3::S3Client;
+use tokio::runtime::Runtime;
+use crate::errors::models::error_response::ErrorResponse;
+use crate::s3::download::download_to_file;
+use crate::s3::models::etag::{self, ETagHasher};
+use crate::s3::models::object_filter::ObjectFilter;
+use crate::s3::models::operation::{OperationOptions, PlannedOperation};
+use crate::s3::models::s3_location::S3Location;
+use crate::s3::models::sync::{compare, Comparison, KeyFilter, SyncAction, SyncChange, SyncEndpoint, SyncEntry};
+use crate::s3::models::sync::{SyncFailure, SyncOptions, SyncReason, SyncReport};
+use crate::s3::operations::{as_prefix, delete_batch, execute_copy, MAX_DELETE_BATCH};
+use crate::s3::s3::s3_list;
+use crate::s3::upload::upload_file;
+
+/// Makes `destination` match `source`, in either direction between a local
+/// directory and an S3 prefix, or between two S3 prefixes.
+pub fn sync(client: &S3Client, source: &SyncEndpoint, destination: &SyncEndpoint, options: &SyncOptions) -> Result<SyncReport, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let actions = plan(client, source, destination, options).await?;
+        Ok(execute(client, actions, options).await)
+    })
+}
+
+pub(crate) async fn plan(client: &S3Client, source: &SyncEndpoint, destination: &SyncEndpoint, options: &SyncOptions) -> Result<Vec<SyncAction>, String> {
+    if let (SyncEndpoint::Local(_), SyncEndpoint::Local(_)) = (source, destination) {
+        return Err(ErrorResponse::json("Sync between two local directories is not supported"));
+    }
+    let filter = KeyFilter::from(options).map_err(|e| ErrorResponse::json(e.as_str()))?;
+    let mut source_entries = entries(client, source, &filter).await?;
+    let mut destination_entries = entries(client, destination, &filter).await?;
+
+    if options.comparison == Comparison::Checksum {
+        if let SyncEndpoint::Local(root) = source {
+            source_entries = with_local_e_tags(root, source_entries, &destination_entries, options).await?;
+        }
+        if let SyncEndpoint::Local(root) = destination {
+            destination_entries = with_local_e_tags(root, destination_entries, &source_entries, options).await?;
+        }
+    }
+
+    let changes = compare(&source_entries, &destination_entries, options.comparison, options.delete);
+    changes.iter().map(|change| to_action(change, source, destination, &source_entries)).collect()
+}
+
+fn to_action(change: &SyncChange, source: &SyncEndpoint, destination: &SyncEndpoint, source_entries: &BTreeMap<String, SyncEntry>) -> Result<SyncAction, String> {
+    if change.reason == SyncReason::Extraneous {
+        return Ok(match destination {
+            SyncEndpoint::Local(root) => SyncAction::DeleteLocal { path: local_path(root, &change.key)?.to_string_lossy().to_string() },
+            SyncEndpoint::S3(location) => SyncAction::DeleteRemote { bucket: location.bucket.clone(), key: format!("{}{}", as_prefix(&location.key), change.key) },
+        });
+    }
+
+    let size = source_entries.get(&change.key).map_or(0, |entry| entry.size);
+    Ok(match (source, destination) {
+        (SyncEndpoint::Local(root), SyncEndpoint::S3(location)) => SyncAction::Upload {
+            path: local_path(root, &change.key)?.to_string_lossy().to_string(),
+            bucket: location.bucket.clone(),
+            key: format!("{}{}", as_prefix(&location.key), change.key),
+            size,
+            reason: change.reason,
+        },
+        (SyncEndpoint::S3(location), SyncEndpoint::Local(root)) => SyncAction::Download {
+            bucket: location.bucket.clone(),
+            key: format!("{}{}", as_prefix(&location.key), change.key),
+            path: local_path(root, &change.key)?.to_string_lossy().to_string(),
+            size,
+            reason: change.reason,
+        },
+        (SyncEndpoint::S3(from), SyncEndpoint::S3(to)) => SyncAction::Copy {
+            source_bucket: from.bucket.clone(),
+            source_key: format!("{}{}", as_prefix(&from.key), change.key),
+            destination_bucket: to.bucket.clone(),
+            destination_key: format!("{}{}", as_prefix(&to.key), change.key),
+            size,
+            reason: change.reason,
+        },
+        (SyncEndpoint::Local(_), SyncEndpoint::Local(_)) => unreachable!("local to local sync is rejected when planning"),
+    })
+}
+
+pub(crate) async fn execute(client: &S3Client, actions: Vec<SyncAction>, options: &SyncOptions) -> SyncReport {
+    let mut report = SyncReport { dry_run: options.dry_run, ..SyncReport::default() };
+    if options.dry_run {
+        report.actions = actions;
+        return report;
+    }
+
+    let results = stream::iter(actions.iter()
+        .filter(|action| !matches!(action, SyncAction::DeleteRemote { .. }))
+        .map(|action| execute_action(client, action, options)))
+        .buffer_unordered(options.concurrency.max(1))
+        .collect::<Vec<Result<(), SyncFailure>>>().await;
+    results.into_iter().for_each(|result| match result {
+        Ok(()) => report.completed += 1,
+        Err(failure) => report.failures.push(failure),
+    });
+
+    let mut remote_deletes: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    actions.iter().for_each(|action| if let SyncAction::DeleteRemote { bucket, key } = action {
+        remote_deletes.entry(bucket.clone()).or_default().push(key.clone());
+    });
+    for (bucket, keys) in remote_deletes {
+        for batch in keys.chunks(MAX_DELETE_BATCH) {
+            let (completed, failures) = delete_batch(client, &bucket, batch).await;
+            report.completed += completed;
+            failures.into_iter().for_each(|failure| report.failures.push(SyncFailure {
+                action: SyncAction::DeleteRemote { bucket: failure.bucket, key: failure.key },
+                message: failure.message,
+            }));
+        }
+    }
+    report.actions = actions;
+    report
+}
+
+async fn execute_action(client: &S3Client, action: &SyncAction, options: &SyncOptions) -> Result<(), SyncFailure> {
+    let result = match action {
+        SyncAction::Upload { path, bucket, key, .. } => {
+            let location = S3Location::new(bucket, key);
+            upload_file(client, Path::new(path), &location, &options.upload).await.map(|_| ())
+        },
+        SyncAction::Download { bucket, key, path, .. } => {
+            let location = S3Location::new(bucket, key);
+            download_to_file(client, &location, Path::new(path), &options.download).await.map(|_| ())
+        },
+        SyncAction::Copy { source_bucket, source_key, destination_bucket, destination_key, size, .. } => {
+            let operation = PlannedOperation::Copy {
+                source_bucket: source_bucket.clone(),
+                source_key: source_key.clone(),
+                source_version_id: None,
+                destination_bucket: destination_bucket.clone(),
+                destination_key: destination_key.clone(),
+                size: *size as i64,
+            };
+            execute_copy(client, &operation, &OperationOptions::default()).await.map_err(|failure| failure.message)
+        },
+        SyncAction::DeleteLocal { path } => tokio::fs::remove_file(path).await.map_err(|e| e.to_string()),
+        SyncAction::DeleteRemote { .. } => Ok(()),
+    };
+    result.map_err(|message| SyncFailure { action: action.clone(), message })
+}
+
+async fn entries(client: &S3Client, endpoint: &SyncEndpoint, filter: &KeyFilter) -> Result<BTreeMap<String, SyncEntry>, String> {
+    match endpoint {
+        SyncEndpoint::Local(root) => {
+            let (root, filter) = (root.clone(), filter.clone());
+            blocking(move || local_entries(&root, &filter)).await
+        },
+        SyncEndpoint::S3(location) => {
+            let prefix = as_prefix(&location.key);
+            let objects = s3_list(client, &location.bucket, &prefix, &ObjectFilter::new()).await?;
+            Ok(objects.iter()
+                .filter(|object| !object.key.ends_with('/'))
+                .map(|object| (object.key[prefix.len()..].to_string(), SyncEntry {
+                    size: object.size as u64,
+                    modified: object.last_modified_date_time(),
+                    e_tag: object.e_tag.clone(),
+                }))
+                .filter(|(key, _)| filter.allows(key))
+                .collect())
+        },
+    }
+}
+
+pub(crate) fn local_entries(root: &Path, filter: &KeyFilter) -> Result<BTreeMap<String, SyncEntry>, String> {
+    let mut entries = BTreeMap::new();
+    if !root.exists() { return Ok(entries); }
+
+    let mut directories = vec![root.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        for entry in std::fs::read_dir(&directory).map_err(io_error)? {
+            let entry = entry.map_err(io_error)?;
+            let metadata = entry.metadata().map_err(io_error)?;
+            if metadata.is_dir() {
+                directories.push(entry.path());
+            } else if metadata.is_file() {
+                let key = relative_key(root, &entry.path());
+                if filter.allows(&key) {
+                    entries.insert(key, SyncEntry {
+                        size: metadata.len(),
+                        modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+                        e_tag: None,
+                    });
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+async fn with_local_e_tags(root: &Path, local: BTreeMap<String, SyncEntry>, other: &BTreeMap<String, SyncEntry>, options: &SyncOptions) -> Result<BTreeMap<String, SyncEntry>, String> {
+    let (root, other, options) = (root.to_path_buf(), other.clone(), options.clone());
+    blocking(move || {
+        let mut local = local;
+        fill_local_e_tags(&root, &mut local, &other, &options).map(|_| local)
+    }).await
+}
+
+/// Runs filesystem walks and hashing on tokio's blocking pool, so a large tree
+/// doesn't hold up the runtime's workers.
+async fn blocking<T: Send + 'static>(work: impl FnOnce() -> Result<T, String> + Send + 'static) -> Result<T, String> {
+    tokio::task::spawn_blocking(work).await.map_err(|e| ErrorResponse::json(e.to_string().as_str()))?
+}
+
+/// Hashes local files that have a same-sized counterpart on the other side. Where the
+/// remote ETag is multipart, the part size this library would upload with is assumed.
+fn fill_local_e_tags(root: &Path, local: &mut BTreeMap<String, SyncEntry>, other: &BTreeMap<String, SyncEntry>, options: &SyncOptions) -> Result<(), String> {
+    for (key, entry) in local.iter_mut() {
+        if let Some(SyncEntry { size, e_tag: Some(other_e_tag), .. }) = other.get(key) {
+            if *size != entry.size { continue; }
+            let part_size = if etag::is_multipart(other_e_tag) { Some(options.upload.part_size_for(Some(entry.size))) } else { None };
+            entry.e_tag = Some(file_e_tag(&local_path(root, key)?, part_size)?);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn file_e_tag(path: &Path, part_size: Option<u64>) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(io_error)?;
+    let mut hasher = ETagHasher::new(part_size);
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer).map_err(io_error)?;
+        if read == 0 { break; }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finish())
+}
+
+pub(crate) fn relative_key(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path).components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+/// The file a key maps to under `root`. Keys with empty, `.` or `..` segments, or
+/// segments that are themselves absolute paths, are rejected so that no key can
+/// reach outside `root`.
+pub(crate) fn local_path(root: &Path, key: &str) -> Result<PathBuf, String> {
+    key.split('/').try_fold(root.to_path_buf(), |path, segment| {
+        let mut components = Path::new(segment).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) => Ok(path.join(segment)),
+            _ => Err(ErrorResponse::json(format!("Key {} does not map to a path under {}", key, root.display()).as_str())),
+        }
+    })
+}
+
+pub(crate) fn io_error(e: std::io::Error) -> String {
+    ErrorResponse::json(e.to_string().as_str())
+}
+
+#[test]
+fn relative_keys_use_forward_slashes() {
+    let root = Path::new("/data/out");
+    assert_eq!("a/b.csv", relative_key(root, &root.join("a").join("b.csv")));
+    assert_eq!(root.join("a").join("b.csv"), local_path(root, "a/b.csv").unwrap());
+}
+
+#[test]
+fn keys_cannot_escape_the_local_root() {
+    let root = Path::new("/data/out");
+    assert!(local_path(root, "../../etc/cron.d/x").is_err());
+    assert!(local_path(root, "a/../../x").is_err());
+    assert!(local_path(root, "a/./b").is_err());
+    assert!(local_path(root, "a//b").is_err());
+    assert!(local_path(root, "/etc/passwd").is_err());
+    assert!(local_path(root, "").is_err());
+    assert_eq!(root.join("a..b").join("..c"), local_path(root, "a..b/..c").unwrap());
+}
@@ -0,0 +1,173 @@
+use std::path::Path;
+use futures::future::try_join_all;
+use rusoto_s3::{AbortMultipartUploadRequest, CompleteMultipartUploadRequest, CompletedMultipartUpload, CompletedPart};
+use rusoto_s3::{CreateMultipartUploadRequest, PutObjectRequest, UploadPartRequest, S3, S3Client, StreamingBody};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::runtime::Runtime;
+use crate::errors::models::error_response::ErrorResponse;
+use crate::s3::models::s3_location::S3Location;
+use crate::s3::models::upload::{content_type_for, UploadOptions, UploadReport};
+
+pub fn put_file(client: &S3Client, path: &Path, location: &S3Location, options: &UploadOptions) -> Result<UploadReport, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async { upload_file(client, path, location, options).await })
+}
+
+pub fn put_bytes(client: &S3Client, data: &[u8], location: &S3Location, options: &UploadOptions) -> Result<UploadReport, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let mut reader = data;
+        put_reader(client, &mut reader, Some(data.len() as u64), location, options).await
+    })
+}
+
+pub(crate) async fn upload_file(client: &S3Client, path: &Path, location: &S3Location, options: &UploadOptions) -> Result<UploadReport, String> {
+    let size = tokio::fs::metadata(path).await.map_err(io_error)?.len();
+    let mut file = tokio::fs::File::open(path).await.map_err(io_error)?;
+    put_reader(client, &mut file, Some(size), location, options).await
+}
+
+/// Uploads everything `reader` yields. Content up to `options.multipart_threshold`
+/// goes up in a single PutObject; anything larger switches to a multipart upload,
+/// sending up to `options.concurrency` parts at a time and aborting the upload
+/// if any part fails. `size`, when known, is used to keep within the part limit.
+pub async fn put_reader<R: AsyncRead + Unpin>(client: &S3Client, reader: &mut R, size: Option<u64>, location: &S3Location, options: &UploadOptions) -> Result<UploadReport, String> {
+    let first = read_up_to(reader, options.multipart_threshold + 1).await?;
+    if first.len() as u64 <= options.multipart_threshold {
+        return put_single(client, first, location, options).await;
+    }
+
+    let upload_id = create_multipart(client, location, options).await?;
+    let mut remaining = (&first[..]).chain(reader);
+    match put_parts(client, &mut remaining, size, location, &upload_id, options).await {
+        Ok(report) => Ok(report),
+        Err(e) => {
+            let _ = client.abort_multipart_upload(AbortMultipartUploadRequest {
+                bucket: location.bucket.clone(),
+                key: location.key.clone(),
+                upload_id: upload_id.clone(),
+                ..AbortMultipartUploadRequest::default()
+            }).await;
+            Err(e)
+        }
+    }
+}
+
+async fn put_single(client: &S3Client, data: Vec<u8>, location: &S3Location, options: &UploadOptions) -> Result<UploadReport, String> {
+    let bytes = data.len() as u64;
+    let sse = options.server_side_encryption.as_ref();
+    let output = client.put_object(PutObjectRequest {
+        bucket: location.bucket.clone(),
+        key: location.key.clone(),
+        content_length: Some(bytes as i64),
+        body: Some(StreamingBody::from(data)),
+        content_type: Some(content_type(location, options)),
+        storage_class: options.storage_class.clone(),
+        server_side_encryption: sse.map(|s| s.as_str().to_string()),
+        ssekms_key_id: sse.and_then(|s| s.kms_key_id()),
+        metadata: options.metadata(),
+        tagging: options.tagging(),
+        ..PutObjectRequest::default()
+    }).await.map_err(|e| ErrorResponse::json(e.to_string().as_str()))?;
+
+    Ok(UploadReport {
+        bucket: location.bucket.clone(),
+        key: location.key.clone(),
+        bytes,
+        parts: 1,
+        e_tag: output.e_tag,
+        version_id: output.version_id,
+    })
+}
+
+async fn create_multipart(client: &S3Client, location: &S3Location, options: &UploadOptions) -> Result<String, String> {
+    let sse = options.server_side_encryption.as_ref();
+    let output = client.create_multipart_upload(CreateMultipartUploadRequest {
+        bucket: location.bucket.clone(),
+        key: location.key.clone(),
+        content_type: Some(content_type(location, options)),
+        storage_class: options.storage_class.clone(),
+        server_side_encryption: sse.map(|s| s.as_str().to_string()),
+        ssekms_key_id: sse.and_then(|s| s.kms_key_id()),
+        metadata: options.metadata(),
+        tagging: options.tagging(),
+        ..CreateMultipartUploadRequest::default()
+    }).await.map_err(|e| ErrorResponse::json(e.to_string().as_str()))?;
+
+    match output.upload_id {
+        Some(upload_id) => Ok(upload_id),
+        None => Err(ErrorResponse::json("No upload id returned for multipart upload")),
+    }
+}
+
+async fn put_parts<R: AsyncRead + Unpin>(client: &S3Client, reader: &mut R, size: Option<u64>, location: &S3Location, upload_id: &str, options: &UploadOptions) -> Result<UploadReport, String> {
+    let part_size = options.part_size_for(size);
+    let concurrency = options.concurrency.max(1);
+    let mut pending: Vec<Vec<u8>> = vec![];
+    let mut completed: Vec<CompletedPart> = vec![];
+    let mut bytes = 0;
+    let mut exhausted = false;
+
+    while !exhausted || !pending.is_empty() {
+        while !exhausted && pending.len() < concurrency {
+            let part = read_up_to(reader, part_size).await?;
+            if (part.len() as u64) < part_size { exhausted = true; }
+            if !part.is_empty() { pending.push(part); }
+        }
+        let batch: Vec<Vec<u8>> = pending.drain(..pending.len().min(concurrency)).collect();
+        let first_part_number = completed.len() as i64 + 1;
+        bytes += batch.iter().map(|p| p.len() as u64).sum::<u64>();
+        let mut uploaded = try_join_all(batch.into_iter().enumerate()
+            .map(|(i, data)| put_part(client, location, upload_id, first_part_number + i as i64, data))).await?;
+        completed.append(&mut uploaded);
+    }
+
+    let parts = completed.len();
+    let output = client.complete_multipart_upload(CompleteMultipartUploadRequest {
+        bucket: location.bucket.clone(),
+        key: location.key.clone(),
+        upload_id: upload_id.to_string(),
+        multipart_upload: Some(CompletedMultipartUpload { parts: Some(completed) }),
+        ..CompleteMultipartUploadRequest::default()
+    }).await.map_err(|e| ErrorResponse::json(e.to_string().as_str()))?;
+
+    Ok(UploadReport {
+        bucket: location.bucket.clone(),
+        key: location.key.clone(),
+        bytes,
+        parts,
+        e_tag: output.e_tag,
+        version_id: output.version_id,
+    })
+}
+
+async fn put_part(client: &S3Client, location: &S3Location, upload_id: &str, part_number: i64, data: Vec<u8>) -> Result<CompletedPart, String> {
+    let output = client.upload_part(UploadPartRequest {
+        bucket: location.bucket.clone(),
+        key: location.key.clone(),
+        upload_id: upload_id.to_string(),
+        part_number,
+        content_length: Some(data.len() as i64),
+        body: Some(StreamingBody::from(data)),
+        ..UploadPartRequest::default()
+    }).await.map_err(|e| ErrorResponse::json(e.to_string().as_str()))?;
+
+    Ok(CompletedPart { e_tag: output.e_tag, part_number: Some(part_number) })
+}
+
+fn content_type(location: &S3Location, options: &UploadOptions) -> String {
+    match &options.content_type {
+        Some(content_type) => content_type.clone(),
+        None => content_type_for(&location.key).to_string(),
+    }
+}
+
+pub(crate) async fn read_up_to<R: AsyncRead + Unpin>(reader: &mut R, limit: u64) -> Result<Vec<u8>, String> {
+    let mut buffer = vec![];
+    reader.take(limit).read_to_end(&mut buffer).await.map_err(io_error)?;
+    Ok(buffer)
+}
+
+fn io_error(e: std::io::Error) -> String {
+    ErrorResponse::json(e.to_string().as_str())
+}
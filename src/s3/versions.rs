@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, Utc};
+use rusoto_s3::{ListObjectVersionsRequest, S3, S3Client};
+use tokio::runtime::Runtime;
+use crate::errors::models::error_response::ErrorResponse;
+use crate::s3::models::operation::{OperationOptions, OperationReport, PlannedOperation};
+use crate::s3::models::s3_location::S3Location;
+use crate::s3::models::s3_object_version::S3ObjectVersion;
+use crate::s3::operations::execute;
+
+/// Every version and delete marker under the location's key prefix, ordered by
+/// key and then newest first.
+pub fn ls_versions(client: &S3Client, location: &S3Location) -> Result<Vec<S3ObjectVersion>, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async { list_versions(client, &location.bucket, &location.key).await })
+}
+
+/// Makes the prefix look as it did at `as_of`: keys that have changed since are
+/// copied back from the version current at that time, and keys created since are
+/// deleted. Versions stay intact, so the restore can itself be undone. With
+/// `options.dry_run` the operations are only planned.
+pub fn restore_prefix_as_of(client: &S3Client, location: &S3Location, as_of: DateTime<Utc>, options: &OperationOptions) -> Result<OperationReport, String> {
+    let mut rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let versions = list_versions(client, &location.bucket, &location.key).await?;
+        let planned = plan_restore_as_of(&location.bucket, &versions, as_of);
+        Ok(execute(client, planned, options).await)
+    })
+}
+
+pub(crate) async fn list_versions(client: &S3Client, bucket: &str, prefix: &str) -> Result<Vec<S3ObjectVersion>, String> {
+    let mut versions: Vec<S3ObjectVersion> = vec![];
+    let mut key_marker = None;
+    let mut version_id_marker = None;
+    loop {
+        let output = client.list_object_versions(ListObjectVersionsRequest {
+            bucket: bucket.to_string(),
+            prefix: Some(prefix.to_string()),
+            key_marker: key_marker.clone(),
+            version_id_marker: version_id_marker.clone(),
+            ..ListObjectVersionsRequest::default()
+        }).await.map_err(|e| ErrorResponse::json(e.to_string().as_str()))?;
+
+        versions.extend(output.versions.unwrap_or_default().iter().map(S3ObjectVersion::from_version));
+        versions.extend(output.delete_markers.unwrap_or_default().iter().map(S3ObjectVersion::from_delete_marker));
+        if !output.is_truncated.unwrap_or(false) { break; }
+        key_marker = output.next_key_marker;
+        version_id_marker = output.next_version_id_marker;
+    }
+    sort_versions(&mut versions);
+    Ok(versions)
+}
+
+/// By key, then newest first.
+pub(crate) fn sort_versions(versions: &mut [S3ObjectVersion]) {
+    versions.sort_by(|a, b| a.key.cmp(&b.key).then_with(|| b.recency().cmp(&a.recency())));
+}
+
+pub(crate) fn plan_restore_as_of(bucket: &str, versions: &[S3ObjectVersion], as_of: DateTime<Utc>) -> Vec<PlannedOperation> {
+    let mut by_key: BTreeMap<&str, Vec<&S3ObjectVersion>> = BTreeMap::new();
+    versions.iter().for_each(|v| by_key.entry(v.key.as_str()).or_default().push(v));
+
+    by_key.into_iter().flat_map(|(key, key_versions)| {
+        let current = key_versions.iter().find(|v| v.is_latest);
+        let target = key_versions.iter()
+            .filter(|v| matches!(v.last_modified_date_time(), Some(t) if t <= as_of))
+            .max_by_key(|v| v.recency());
+
+        match (target, current) {
+            (Some(t), Some(c)) if t.version_id == c.version_id => None,
+            (Some(t), _) if !t.is_delete_marker => Some(PlannedOperation::Copy {
+                source_bucket: bucket.to_string(),
+                source_key: key.to_string(),
+                source_version_id: Some(t.version_id.clone()),
+                destination_bucket: bucket.to_string(),
+                destination_key: key.to_string(),
+                size: t.size,
+            }),
+            (_, Some(c)) if !c.is_delete_marker => Some(PlannedOperation::Delete {
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+            }),
+            _ => None,
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+fn version(key: &str, version_id: &str, last_modified: &str, is_latest: bool, is_delete_marker: bool) -> S3ObjectVersion {
+    S3ObjectVersion {
+        key: key.to_string(),
+        version_id: version_id.to_string(),
+        last_modified: last_modified.to_string(),
+        is_latest,
+        is_delete_marker,
+        size: 10,
+        e_tag: None,
+        storage_class: None,
+    }
+}
+
+#[test]
+fn restore_copies_back_overwritten_keys() {
+    let versions = vec![
+        version("a", "v2", "2020-01-03T00:00:00.000Z", true, false),
+        version("a", "v1", "2020-01-01T00:00:00.000Z", false, false),
+    ];
+    let as_of = DateTime::parse_from_rfc3339("2020-01-02T00:00:00Z").unwrap().with_timezone(&Utc);
+    assert_eq!(vec![PlannedOperation::Copy {
+        source_bucket: "b".to_string(),
+        source_key: "a".to_string(),
+        source_version_id: Some("v1".to_string()),
+        destination_bucket: "b".to_string(),
+        destination_key: "a".to_string(),
+        size: 10,
+    }], plan_restore_as_of("b", &versions, as_of));
+}
+
+#[test]
+fn restore_undeletes_and_deletes() {
+    let versions = vec![
+        version("deleted", "d1", "2020-01-03T00:00:00.000Z", true, true),
+        version("deleted", "v1", "2020-01-01T00:00:00.000Z", false, false),
+        version("created", "v1", "2020-01-03T00:00:00.000Z", true, false),
+        version("unchanged", "v1", "2020-01-01T00:00:00.000Z", true, false),
+        version("gone", "d1", "2020-01-01T12:00:00.000Z", true, true),
+        version("gone", "v1", "2020-01-01T00:00:00.000Z", false, false),
+    ];
+    let as_of = DateTime::parse_from_rfc3339("2020-01-02T00:00:00Z").unwrap().with_timezone(&Utc);
+    let planned = plan_restore_as_of("b", &versions, as_of);
+
+    assert_eq!(2, planned.len());
+    assert_eq!(PlannedOperation::Delete { bucket: "b".to_string(), key: "created".to_string() }, planned[0]);
+    match &planned[1] {
+        PlannedOperation::Copy { source_key, source_version_id, .. } => {
+            assert_eq!("deleted", source_key.as_str());
+            assert_eq!(&Some("v1".to_string()), source_version_id);
+        },
+        _ => panic!("expected a copy"),
+    }
+}
+
+#[test]
+fn versions_sort_by_parsed_time_then_latest() {
+    let mut versions = vec![
+        version("a", "v1", "2020-01-01T00:00:00Z", false, false),
+        version("a", "v2", "2020-01-01T00:00:00.500Z", false, false),
+        version("a", "v3", "2020-01-01T00:00:00.500Z", true, true),
+    ];
+    sort_versions(&mut versions);
+    assert_eq!(vec!["v3", "v2", "v1"], versions.iter().map(|v| v.version_id.as_str()).collect::<Vec<&str>>());
+}
+
+#[test]
+fn restore_prefers_the_latest_of_simultaneous_versions() {
+    let versions = vec![
+        version("a", "v2", "2020-01-01T00:00:00.000Z", true, false),
+        version("a", "v1", "2020-01-01T00:00:00.000Z", false, false),
+    ];
+    let as_of = DateTime::parse_from_rfc3339("2020-01-02T00:00:00Z").unwrap().with_timezone(&Utc);
+    assert!(plan_restore_as_of("b", &versions, as_of).is_empty());
+}